@@ -61,14 +61,12 @@ fn main() {
                 }),
                 ..default()
             }),
-            PixelsPlugin {
-                primary_window: Some(PixelsOptions {
-                    width: INITIAL_WIDTH,
-                    height: INITIAL_HEIGHT,
-                    scale_factor: SCALE_FACTOR,
-                    ..default()
-                }),
-            },
+            PixelsPlugin::default().with_primary_window(Some(PixelsOptions {
+                width: INITIAL_WIDTH,
+                height: INITIAL_HEIGHT,
+                scale_factor: SCALE_FACTOR,
+                ..default()
+            })),
             FrameTimeDiagnosticsPlugin::default(),
             LogDiagnosticsPlugin::default(),
         ))
@@ -2,7 +2,7 @@ use bevy::{
     input::ButtonInput,
     math::DVec2,
     prelude::*,
-    window::{PresentMode, PrimaryWindow, WindowResolution},
+    window::{PresentMode, PrimaryWindow, RequestRedraw, WindowResolution},
     winit::WinitSettings,
 };
 use bevy_pixels::prelude::*;
@@ -36,20 +36,19 @@ fn main() {
     App::new()
         .insert_resource(MandelbrotView::default())
         .insert_resource(WinitSettings::game())
+        // Rendering the set is expensive, so only redraw while zooming or after a resize.
+        .insert_resource(PixelsRedrawPolicy::OnDemand)
         .add_plugins((
             DefaultPlugins.set(WindowPlugin {
                 primary_window: Some(primary_window()),
                 ..default()
             }),
-            PixelsPlugin {
-                primary_window: Some(PixelsOptions {
-                    width: BUFFER_WIDTH,
-                    height: BUFFER_HEIGHT,
-                    scale_factor: SCALE_FACTOR,
-                    auto_resize_buffer: true,
-                    auto_resize_surface: true,
-                }),
-            },
+            PixelsPlugin::default().with_primary_window(Some(PixelsOptions {
+                width: BUFFER_WIDTH,
+                height: BUFFER_HEIGHT,
+                scale_factor: SCALE_FACTOR,
+                ..default()
+            })),
         ))
         .add_systems(Update, zoom_view)
         .add_systems(Draw, draw)
@@ -86,6 +85,7 @@ fn zoom_view(
     time: Res<Time>,
     window: Single<(&Window, &PixelsOptions), With<PrimaryWindow>>,
     mut view: ResMut<MandelbrotView>,
+    mut redraw: MessageWriter<RequestRedraw>,
 ) {
     let zoom_direction =
         if buttons.pressed(MouseButton::Left) && !buttons.pressed(MouseButton::Right) {
@@ -110,27 +110,28 @@ fn zoom_view(
 
     view.center = center_for_focus(focus, x_ratio, y_ratio, new_width, aspect_ratio);
     view.width = new_width;
+    redraw.write(RequestRedraw);
 }
 
-fn draw(mut wrapper: Single<(&mut PixelsWrapper, &PixelsOptions)>, view: Res<MandelbrotView>) {
+fn draw(
+    mut wrapper: Single<(&mut PixelsWrapper, &PixelsOptions)>,
+    view: Res<MandelbrotView>,
+) -> Result {
     let (wrapper, options) = &mut *wrapper;
-    let frame = wrapper.pixels.frame_mut();
-    let width = options.width as usize;
-    let aspect_ratio = options.height as f64 / options.width as f64;
-
-    for y in 0..options.height {
-        for x in 0..options.width {
-            let point = complex_at(
-                &view,
-                (x as f64 + 0.5) / options.width as f64,
-                (y as f64 + 0.5) / options.height as f64,
-                aspect_ratio,
-            );
-            let color = mandelbrot_color(point);
-            let index = ((y as usize * width) + x as usize) * 4;
-            frame[index..index + 4].copy_from_slice(&color);
-        }
-    }
+    let (width, height) = (options.width as f64, options.height as f64);
+    let aspect_ratio = height / width;
+
+    // Each pixel is independent, so compute row bands on all cores.
+    wrapper.par_pixels_mut(|position, pixel| {
+        let point = complex_at(
+            &view,
+            (position.x as f64 + 0.5) / width,
+            (position.y as f64 + 0.5) / height,
+            aspect_ratio,
+        );
+        *pixel = mandelbrot_color(point);
+    })?;
+    Ok(())
 }
 
 fn complex_at(view: &MandelbrotView, x_ratio: f64, y_ratio: f64, aspect_ratio: f64) -> DVec2 {
@@ -1,25 +1,57 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, window::PrimaryWindow};
 use bevy_pixels::prelude::*;
 
+/// Marker for the window that displays the map.
+#[derive(Component)]
+struct MapWindow;
+
+/// Marker for the window that displays debug information.
+#[derive(Component)]
+struct DebugWindow;
+
 fn main() {
     App::new()
         .add_plugins((DefaultPlugins, PixelsPlugin::default()))
         .add_systems(Startup, setup)
-        .add_systems(Draw, draw)
+        .add_systems(Draw, (draw_main, draw_map, draw_debug))
         .run();
 }
 
 /// Spawn two more windows in addition to the primary window that comes by default.
 fn setup(mut commands: Commands) {
-    commands.spawn((Window::default(), PixelsOptions::default()));
-    commands.spawn((Window::default(), PixelsOptions::default()));
+    commands.spawn((
+        Window {
+            title: "Map".to_string(),
+            ..default()
+        },
+        PixelsOptions::default(),
+        MapWindow,
+    ));
+    commands.spawn((
+        Window {
+            title: "Debug".to_string(),
+            ..default()
+        },
+        PixelsOptions::default(),
+        DebugWindow,
+    ));
+}
+
+/// Draw solid background to the primary window's buffer.
+fn draw_main(mut target: DrawTarget<PrimaryWindow>) {
+    fill(target.frame_mut(), [0x48, 0xb2, 0xe8, 0xff]);
 }
 
-/// Draw solid background to each window's buffer.
-fn draw(mut wrapper_query: Query<&mut PixelsWrapper>) {
-    for mut wrapper in &mut wrapper_query {
-        let frame = wrapper.pixels.frame_mut();
+/// Draw solid background to the map window's buffer.
+fn draw_map(mut target: DrawTarget<MapWindow>) {
+    fill(target.frame_mut(), [0x5e, 0x99, 0x48, 0xff]);
+}
+
+/// Draw solid background to the debug window's buffer.
+fn draw_debug(mut target: DrawTarget<DebugWindow>) {
+    fill(target.frame_mut(), [0x20, 0x20, 0x20, 0xff]);
+}
 
-        frame.copy_from_slice(&[0x48, 0xb2, 0xe8, 0xff].repeat(frame.len() / 4));
-    }
+fn fill(frame: &mut [u8], color: [u8; 4]) {
+    frame.copy_from_slice(&color.repeat(frame.len() / 4));
 }
@@ -0,0 +1,192 @@
+use crate::{PixelFormat, convert_pixels};
+
+use bevy::{math::I64Vec2, prelude::*};
+
+/// Offscreen RGBA8 pixel buffer for entities with [`PixelsOptions`](crate::PixelsOptions) but no
+/// [`Window`]. Useful as a render target for minimaps, portraits or precomputed backgrounds which
+/// are then blitted into window buffers.
+///
+/// This component is created automatically and resized whenever the entity's `PixelsOptions`
+/// dimensions change. Window related options are ignored for offscreen buffers.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct PixelsBuffer {
+    width: u32,
+    height: u32,
+    frame: Vec<u8>,
+}
+
+impl PixelsBuffer {
+    /// Create a buffer of the given size, cleared to transparent black.
+    pub fn new(width: u32, height: u32) -> Self {
+        PixelsBuffer {
+            width,
+            height,
+            frame: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Width of the buffer in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the buffer in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get a mutable byte slice for the pixel buffer.
+    pub fn frame_mut(&mut self) -> &mut [u8] {
+        &mut self.frame
+    }
+
+    /// Get an immutable byte slice for the pixel buffer.
+    pub fn frame(&self) -> &[u8] {
+        &self.frame
+    }
+
+    /// Resize the buffer. Existing contents are discarded and the buffer is cleared.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        *self = PixelsBuffer::new(width, height);
+    }
+
+    /// Copy this buffer into `target` (an RGBA8 frame of `target_width` by `target_height`) with
+    /// its top-left corner at `position`. Pixels falling outside the target are clipped.
+    pub fn blit(&self, target: &mut [u8], target_width: u32, target_height: u32, position: IVec2) {
+        blit(
+            &self.frame,
+            UVec2::new(self.width, self.height),
+            target,
+            UVec2::new(target_width, target_height),
+            PixelFormat::Rgba8,
+            position,
+        );
+    }
+}
+
+/// Copy an RGBA8 `source` frame into a `target` frame of `format` pixels at `position`, clipping
+/// to the target.
+pub(crate) fn blit(
+    source: &[u8],
+    source_size: UVec2,
+    target: &mut [u8],
+    target_size: UVec2,
+    format: PixelFormat,
+    position: IVec2,
+) {
+    let source_size = source_size.as_i64vec2();
+    let target_size = target_size.as_i64vec2();
+    let position = position.as_i64vec2();
+
+    let start = position.max(I64Vec2::ZERO);
+    let end = (position + source_size).min(target_size);
+    if start.x >= end.x || start.y >= end.y {
+        return;
+    }
+
+    let bytes_per_pixel = format.bytes_per_pixel();
+    let row_pixels = (end.x - start.x) as usize;
+    for y in start.y..end.y {
+        let source_x = (start.x - position.x) as usize;
+        let source_y = (y - position.y) as usize;
+        let source_offset = (source_y * source_size.x as usize + source_x) * 4;
+        let target_offset =
+            (y as usize * target_size.x as usize + start.x as usize) * bytes_per_pixel;
+
+        convert_pixels(
+            &source[source_offset..source_offset + row_pixels * 4],
+            PixelFormat::Rgba8,
+            &mut target[target_offset..target_offset + row_pixels * bytes_per_pixel],
+            format,
+        )
+        .expect("rows have the same number of pixels");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer_with_pixels(width: u32, height: u32) -> PixelsBuffer {
+        let mut buffer = PixelsBuffer::new(width, height);
+        for (index, pixel) in buffer.frame_mut().chunks_exact_mut(4).enumerate() {
+            pixel.copy_from_slice(&[index as u8 + 1; 4]);
+        }
+        buffer
+    }
+
+    fn pixel(frame: &[u8], width: u32, x: u32, y: u32) -> u8 {
+        frame[((y * width + x) * 4) as usize]
+    }
+
+    #[test]
+    fn new_buffer_is_cleared_rgba() {
+        let buffer = PixelsBuffer::new(3, 2);
+
+        assert_eq!((buffer.width(), buffer.height()), (3, 2));
+        assert_eq!(buffer.frame(), &[0; 24]);
+    }
+
+    #[test]
+    fn resize_replaces_frame() {
+        let mut buffer = buffer_with_pixels(2, 2);
+
+        buffer.resize(4, 1);
+
+        assert_eq!(buffer, PixelsBuffer::new(4, 1));
+    }
+
+    #[test]
+    fn blit_copies_inside_target() {
+        let source = buffer_with_pixels(2, 2);
+        let mut target = vec![0; 4 * 4 * 4];
+
+        source.blit(&mut target, 4, 4, IVec2::new(1, 2));
+
+        assert_eq!(pixel(&target, 4, 1, 2), 1);
+        assert_eq!(pixel(&target, 4, 2, 2), 2);
+        assert_eq!(pixel(&target, 4, 1, 3), 3);
+        assert_eq!(pixel(&target, 4, 2, 3), 4);
+        assert_eq!(target.iter().filter(|&&byte| byte != 0).count(), 16);
+    }
+
+    #[test]
+    fn blit_clips_negative_and_overflowing_positions() {
+        let source = buffer_with_pixels(2, 2);
+        let mut target = vec![0; 2 * 2 * 4];
+
+        source.blit(&mut target, 2, 2, IVec2::new(-1, 1));
+
+        assert_eq!(pixel(&target, 2, 0, 1), 2);
+        assert_eq!(target.iter().filter(|&&byte| byte != 0).count(), 4);
+    }
+
+    #[test]
+    fn blit_outside_target_is_a_no_op() {
+        let source = buffer_with_pixels(2, 2);
+        let mut target = vec![0; 2 * 2 * 4];
+
+        source.blit(&mut target, 2, 2, IVec2::new(2, 0));
+        source.blit(&mut target, 2, 2, IVec2::new(0, -2));
+
+        assert!(target.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn blit_converts_to_target_format() {
+        let mut source = PixelsBuffer::new(1, 1);
+        source.frame_mut().copy_from_slice(&[1, 2, 3, 4]);
+        let mut target = vec![0; 2 * 4];
+
+        blit(
+            source.frame(),
+            UVec2::ONE,
+            &mut target,
+            UVec2::new(2, 1),
+            PixelFormat::Bgra8,
+            IVec2::new(1, 0),
+        );
+
+        assert_eq!(target, [0, 0, 0, 0, 3, 2, 1, 4]);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::{
+    post_process::FullscreenPipeline,
+    prelude::*,
+    {PassError, PassTextures},
+};
+
+use bevy::prelude::*;
+use pixels::{PixelsContext, wgpu};
+
+/// Size in bytes of the CRT uniform buffer, matching `CrtUniforms` in `crt.wgsl`.
+const UNIFORM_SIZE: usize = 48;
+
+/// Phosphor mask pattern emulated by [`CrtPass`].
+#[derive(Reflect, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[reflect(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum CrtMask {
+    /// No mask.
+    None,
+    /// Vertical RGB stripes, as on Trinitron displays.
+    #[default]
+    ApertureGrille,
+    /// RGB stripes broken into staggered slots.
+    SlotMask,
+    /// RGB triads offset on alternate rows.
+    ShadowMask,
+}
+
+/// Parameters of the [`CrtPass`] in a window's [`PostProcessChain`]. Changes are applied to the
+/// pass every frame, so this component can be tweaked at runtime.
+#[derive(Component, Reflect, Debug, Copy, Clone, PartialEq)]
+#[reflect(Component, Debug, Default, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct CrtSettings {
+    /// Darkening between buffer rows, from `0.0` (none) to `1.0` (black gaps).
+    pub scanline_intensity: f32,
+    /// Barrel distortion of the screen, `0.0` is flat.
+    pub curvature: f32,
+    /// Darkening towards the screen corners, from `0.0` to `1.0`.
+    pub vignette: f32,
+    /// Glow bleeding from bright pixels into their neighbours.
+    pub bloom: f32,
+    /// Phosphor mask pattern.
+    pub mask: CrtMask,
+    /// Strength of the phosphor mask, from `0.0` to `1.0`.
+    pub mask_intensity: f32,
+}
+
+impl Default for CrtSettings {
+    fn default() -> Self {
+        CrtSettings {
+            scanline_intensity: 0.5,
+            curvature: 0.1,
+            vignette: 0.3,
+            bloom: 0.2,
+            mask: CrtMask::ApertureGrille,
+            mask_intensity: 0.2,
+        }
+    }
+}
+
+impl CrtSettings {
+    /// Pack these settings into the uniform layout expected by `crt.wgsl`. `image_rect` is the
+    /// area (x, y, width, height) of the output covered by the scaled buffer.
+    pub(crate) fn uniforms(&self, buffer_size: UVec2, image_rect: URect) -> [u8; UNIFORM_SIZE] {
+        let mask_type: u32 = match self.mask {
+            CrtMask::None => 0,
+            CrtMask::ApertureGrille => 1,
+            CrtMask::SlotMask => 2,
+            CrtMask::ShadowMask => 3,
+        };
+        let words = [
+            (image_rect.min.x as f32).to_bits(),
+            (image_rect.min.y as f32).to_bits(),
+            (image_rect.width() as f32).to_bits(),
+            (image_rect.height() as f32).to_bits(),
+            (buffer_size.x as f32).to_bits(),
+            (buffer_size.y as f32).to_bits(),
+            self.scanline_intensity.to_bits(),
+            self.curvature.to_bits(),
+            self.vignette.to_bits(),
+            self.bloom.to_bits(),
+            self.mask_intensity.to_bits(),
+            mask_type,
+        ];
+
+        let mut bytes = [0; UNIFORM_SIZE];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Retro CRT effect with scanlines, curvature, vignette, bloom and a phosphor mask. Should follow
+/// a [`ScalingPass`] in the chain. Configure it with a [`CrtSettings`] component on the window.
+#[derive(Debug, Default)]
+pub struct CrtPass {
+    settings: CrtSettings,
+    pipeline: Option<FullscreenPipeline>,
+}
+
+impl CrtPass {
+    /// Create a pass with the given settings.
+    pub fn new(settings: CrtSettings) -> Self {
+        CrtPass {
+            settings,
+            pipeline: None,
+        }
+    }
+
+    /// Current settings of the pass.
+    pub fn settings(&self) -> &CrtSettings {
+        &self.settings
+    }
+
+    /// Replace the settings of the pass.
+    pub fn set_settings(&mut self, settings: CrtSettings) {
+        self.settings = settings;
+    }
+}
+
+impl PostProcessPass for CrtPass {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        textures: PassTextures,
+        context: &PixelsContext,
+    ) -> Result<(), PassError> {
+        let (x, y, width, height) = context.scaling_renderer.clip_rect();
+        let uniforms = self.settings.uniforms(
+            UVec2::new(context.texture_extent.width, context.texture_extent.height),
+            URect::new(x, y, x + width, y + height),
+        );
+
+        FullscreenPipeline::ensure(
+            &mut self.pipeline,
+            &context.device,
+            "bevy_pixels_crt",
+            include_str!("shaders/crt.wgsl"),
+            textures.output.texture().format(),
+            wgpu::FilterMode::Linear,
+            UNIFORM_SIZE as u64,
+        )
+        .render(
+            encoder,
+            &context.device,
+            &context.queue,
+            textures,
+            &uniforms,
+        );
+        Ok(())
+    }
+}
+
+/// Copy each window's [`CrtSettings`] into the [`CrtPass`] of its [`PostProcessChain`]. Changed
+/// settings mark the chain changed, so static frames are presented again with them.
+pub fn synchronize_crt_settings(mut windows: Query<(Ref<CrtSettings>, &mut PostProcessChain)>) {
+    for (settings, mut chain) in &mut windows {
+        let pass = if settings.is_changed() {
+            chain.pass_mut::<CrtPass>()
+        } else {
+            // Passes added since the last run take the current settings.
+            chain.bypass_change_detection().pass_mut::<CrtPass>()
+        };
+        if let Some(pass) = pass {
+            pass.set_settings(*settings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::post_process::tests::validate_fragment_shader;
+
+    fn word(bytes: &[u8], index: usize) -> [u8; 4] {
+        bytes[index * 4..index * 4 + 4].try_into().unwrap()
+    }
+
+    fn float(bytes: &[u8], index: usize) -> f32 {
+        f32::from_le_bytes(word(bytes, index))
+    }
+
+    #[test]
+    fn uniforms_follow_shader_layout() {
+        let settings = CrtSettings {
+            scanline_intensity: 0.25,
+            curvature: 0.5,
+            vignette: 0.75,
+            bloom: 1.5,
+            mask: CrtMask::SlotMask,
+            mask_intensity: 0.125,
+        };
+
+        let bytes = settings.uniforms(UVec2::new(320, 240), URect::new(16, 8, 656, 488));
+
+        assert_eq!(
+            (0..4).map(|index| float(&bytes, index)).collect::<Vec<_>>(),
+            vec![16.0, 8.0, 640.0, 480.0]
+        );
+        assert_eq!((float(&bytes, 4), float(&bytes, 5)), (320.0, 240.0));
+        assert_eq!(float(&bytes, 6), 0.25);
+        assert_eq!(float(&bytes, 7), 0.5);
+        assert_eq!(float(&bytes, 8), 0.75);
+        assert_eq!(float(&bytes, 9), 1.5);
+        assert_eq!(float(&bytes, 10), 0.125);
+        assert_eq!(u32::from_le_bytes(word(&bytes, 11)), 2);
+    }
+
+    #[test]
+    fn crt_shader_is_valid() {
+        validate_fragment_shader(include_str!("shaders/crt.wgsl"));
+    }
+
+    #[test]
+    fn mask_types_are_numbered_as_in_shader() {
+        let mask_type = |mask| {
+            let bytes =
+                CrtSettings { mask, ..default() }.uniforms(UVec2::ONE, URect::new(0, 0, 1, 1));
+            u32::from_le_bytes(word(&bytes, 11))
+        };
+
+        assert_eq!(mask_type(CrtMask::None), 0);
+        assert_eq!(mask_type(CrtMask::ApertureGrille), 1);
+        assert_eq!(mask_type(CrtMask::SlotMask), 2);
+        assert_eq!(mask_type(CrtMask::ShadowMask), 3);
+    }
+
+    #[test]
+    fn settings_are_copied_into_chain_pass() {
+        let mut world = World::new();
+        let settings = CrtSettings {
+            curvature: 0.0,
+            ..default()
+        };
+        let window = world
+            .spawn((
+                settings,
+                PostProcessChain::new()
+                    .with(ScalingPass)
+                    .with(CrtPass::default()),
+            ))
+            .id();
+
+        world.run_system_cached(synchronize_crt_settings).unwrap();
+
+        let mut chain = world.get_mut::<PostProcessChain>(window).unwrap();
+        assert_eq!(chain.pass_mut::<CrtPass>().unwrap().settings(), &settings);
+    }
+
+    #[derive(Resource, Default)]
+    struct Presented(Vec<bool>);
+
+    #[test]
+    fn changed_settings_present_static_frames() {
+        let mut world = World::new();
+        world.init_resource::<Presented>();
+        let window = world
+            .spawn((
+                CrtSettings::default(),
+                PostProcessChain::new()
+                    .with(ScalingPass)
+                    .with(CrtPass::default()),
+            ))
+            .id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(
+            (
+                synchronize_crt_settings,
+                |chain: Single<Ref<PostProcessChain>>, mut presented: ResMut<Presented>| {
+                    // The wrapper of a static frame is unchanged.
+                    let changed = crate::system::frame_changed(false, None, chain.is_changed());
+                    presented.0.push(changed);
+                },
+            )
+                .chain(),
+        );
+
+        schedule.run(&mut world);
+        schedule.run(&mut world);
+        world
+            .get_mut::<CrtSettings>(window)
+            .unwrap()
+            .scanline_intensity = 0.0;
+        schedule.run(&mut world);
+
+        assert_eq!(world.resource::<Presented>().0, vec![true, false, true]);
+        let chain = world.get::<PostProcessChain>(window).unwrap();
+        assert_eq!(
+            chain
+                .pass::<CrtPass>()
+                .unwrap()
+                .settings()
+                .scanline_intensity,
+            0.0
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::prelude::*;
+
+use bevy::prelude::*;
+
+/// Maximum number of separate dirty rectangles kept before the closest ones are merged.
+pub const MAX_DIRTY_RECTS: usize = 8;
+
+/// Fraction of the frame area above which [`DirtyRects::coverage`] reports the whole frame.
+pub const FULL_FRAME_RATIO: f32 = 0.5;
+
+/// Opt-in tracking of the regions of a window's frame changed since it was last rendered.
+///
+/// [`DrawTarget::frame_rect_mut`] and [`DrawTarget::viewport`] mark only the region they draw
+/// into. Any other mutable access to the [`PixelsWrapper`] (including palettes, mirrors and
+/// resizes) marks the whole frame dirty. The default render system clears the rectangles after
+/// rendering.
+///
+/// The regions are reported by [`DirtyRects::coverage`], for example to redraw or copy only the
+/// changed part of a frame. Texture uploads are not affected: `pixels` 0.17 always uploads the
+/// whole frame while rendering.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct DirtyRects {
+    rects: Vec<URect>,
+    full: bool,
+}
+
+/// Part of a frame changed since the last render, see [`DirtyRects::coverage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirtyCoverage {
+    /// Nothing changed since the last render.
+    None,
+    /// Only these regions changed.
+    Regions(Vec<URect>),
+    /// The whole frame, or most of it, changed.
+    Full,
+}
+
+impl DirtyRects {
+    /// Mark `rect` as changed. Touching or overlapping rectangles are merged, and once more than
+    /// [`MAX_DIRTY_RECTS`] remain the pair whose union adds the least area is merged.
+    pub fn mark(&mut self, rect: URect) {
+        if self.full || rect.is_empty() {
+            return;
+        }
+
+        let mut rect = rect;
+        while let Some(index) = self.rects.iter().position(|other| touches(*other, rect)) {
+            rect = rect.union(self.rects.swap_remove(index));
+        }
+        self.rects.push(rect);
+
+        if self.rects.len() > MAX_DIRTY_RECTS {
+            let (a, b) = self.cheapest_merge();
+            let merged = self.rects[a].union(self.rects[b]);
+            // `b > a`, so removing `b` first keeps `a` valid.
+            self.rects.swap_remove(b);
+            self.rects.swap_remove(a);
+            self.mark(merged);
+        }
+    }
+
+    /// Mark the whole frame as changed.
+    pub fn mark_all(&mut self) {
+        self.full = true;
+        self.rects.clear();
+    }
+
+    /// Whether nothing changed since the last [`clear`](DirtyRects::clear).
+    pub fn is_empty(&self) -> bool {
+        !self.full && self.rects.is_empty()
+    }
+
+    /// Whether the whole frame is marked as changed.
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    /// The changed rectangles, empty when the whole frame is marked.
+    pub fn rects(&self) -> &[URect] {
+        &self.rects
+    }
+
+    /// Forget all changes, usually after rendering.
+    pub fn clear(&mut self) {
+        self.full = false;
+        self.rects.clear();
+    }
+
+    /// Changed part of a frame of `size`. Regions are clipped to the frame, and the whole frame is
+    /// reported when they cover more than [`FULL_FRAME_RATIO`] of it.
+    pub fn coverage(&self, size: UVec2) -> DirtyCoverage {
+        if self.full {
+            return DirtyCoverage::Full;
+        }
+
+        let bounds = URect::from_corners(UVec2::ZERO, size);
+        let regions: Vec<URect> = self
+            .rects
+            .iter()
+            .map(|rect| rect.intersect(bounds))
+            .filter(|rect| !rect.is_empty())
+            .collect();
+        let dirty_area: u64 = regions.iter().map(|rect| area(*rect)).sum();
+
+        if regions.is_empty() {
+            DirtyCoverage::None
+        } else if dirty_area as f32 > area(bounds) as f32 * FULL_FRAME_RATIO {
+            DirtyCoverage::Full
+        } else {
+            DirtyCoverage::Regions(regions)
+        }
+    }
+
+    fn cheapest_merge(&self) -> (usize, usize) {
+        let mut best = (0, 1, u64::MAX);
+        for a in 0..self.rects.len() {
+            for b in a + 1..self.rects.len() {
+                let added = area(self.rects[a].union(self.rects[b]))
+                    - area(self.rects[a])
+                    - area(self.rects[b]);
+                if added < best.2 {
+                    best = (a, b, added);
+                }
+            }
+        }
+        (best.0, best.1)
+    }
+}
+
+/// Mark whole frames dirty when their [`PixelsWrapper`] was mutably accessed without going
+/// through the region tracking of [`DrawTarget`]. Runs in the [`Render`] schedule.
+pub fn track_dirty_frames(mut query: Query<(Ref<PixelsWrapper>, &mut DirtyRects)>) {
+    for (wrapper, mut dirty) in &mut query {
+        if wrapper.is_changed() {
+            dirty.mark_all();
+        }
+    }
+}
+
+/// Whether two rectangles overlap or share an edge, so their union adds no untouched area
+/// between them.
+fn touches(a: URect, b: URect) -> bool {
+    a.min.x <= b.max.x && b.min.x <= a.max.x && a.min.y <= b.max.y && b.min.y <= a.max.y
+}
+
+fn area(rect: URect) -> u64 {
+    let size = rect.size();
+    u64::from(size.x) * u64::from(size.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> URect {
+        URect::new(x, y, x + width, y + height)
+    }
+
+    #[test]
+    fn touching_and_overlapping_rects_merge() {
+        let mut dirty = DirtyRects::default();
+
+        dirty.mark(rect(0, 0, 2, 2));
+        dirty.mark(rect(2, 0, 2, 2));
+        dirty.mark(rect(1, 1, 1, 4));
+        dirty.mark(rect(10, 10, 1, 1));
+
+        assert_eq!(dirty.rects().len(), 2);
+        assert!(dirty.rects().contains(&rect(0, 0, 4, 5)));
+        assert!(dirty.rects().contains(&rect(10, 10, 1, 1)));
+    }
+
+    #[test]
+    fn merge_chains_absorb_every_touching_rect() {
+        let mut dirty = DirtyRects::default();
+
+        dirty.mark(rect(0, 0, 1, 1));
+        dirty.mark(rect(4, 0, 1, 1));
+        dirty.mark(rect(1, 0, 3, 1));
+
+        assert_eq!(dirty.rects(), &[rect(0, 0, 5, 1)]);
+    }
+
+    #[test]
+    fn rect_count_is_bounded_by_merging_nearest_pair() {
+        let mut dirty = DirtyRects::default();
+
+        for index in 0..MAX_DIRTY_RECTS as u32 {
+            dirty.mark(rect(index * 10, 0, 1, 1));
+        }
+        dirty.mark(rect(0, 2, 1, 1));
+
+        assert_eq!(dirty.rects().len(), MAX_DIRTY_RECTS);
+        assert!(dirty.rects().contains(&rect(0, 0, 1, 3)));
+    }
+
+    #[test]
+    fn empty_rects_are_ignored() {
+        let mut dirty = DirtyRects::default();
+
+        dirty.mark(rect(3, 3, 0, 5));
+
+        assert!(dirty.is_empty());
+        assert_eq!(dirty.coverage(UVec2::new(8, 8)), DirtyCoverage::None);
+    }
+
+    #[test]
+    fn coverage_clips_regions_and_falls_back_to_full() {
+        let mut dirty = DirtyRects::default();
+        dirty.mark(rect(6, 6, 4, 4));
+        dirty.mark(rect(20, 20, 2, 2));
+
+        assert_eq!(
+            dirty.coverage(UVec2::new(8, 8)),
+            DirtyCoverage::Regions(vec![rect(6, 6, 2, 2)])
+        );
+
+        dirty.mark(rect(0, 0, 6, 6));
+        assert_eq!(dirty.coverage(UVec2::new(8, 8)), DirtyCoverage::Full);
+    }
+
+    #[test]
+    fn mark_all_overrides_regions_until_cleared() {
+        let mut dirty = DirtyRects::default();
+        dirty.mark_all();
+        dirty.mark(rect(0, 0, 1, 1));
+
+        assert!(dirty.is_full());
+        assert!(dirty.rects().is_empty());
+        assert_eq!(dirty.coverage(UVec2::new(8, 8)), DirtyCoverage::Full);
+
+        dirty.clear();
+        assert!(dirty.is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+use crate::prelude::*;
+
+use bevy::prelude::*;
+
+/// Opt-in copy of the last rendered frame, for effects such as motion trails, feedback and
+/// cellular automata that read the previous frame while writing the next one.
+///
+/// Add it to an entity with a [`PixelsWrapper`] or [`PixelsBuffer`]. The plugin stores the
+/// finished frame at the end of [`Render`], so [`Draw`] systems always see the frame from the
+/// previous redraw. It is empty until the first frame was rendered.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct PreviousFrame {
+    frame: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl PreviousFrame {
+    /// Bytes of the previous frame, in the [`pixel_format`](PixelsOptions::pixel_format) of the
+    /// target.
+    pub fn frame(&self) -> &[u8] {
+        &self.frame
+    }
+
+    /// Width of the previous frame in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the previous frame in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Bytes of the pixel at `position` in the previous frame, if inside the frame.
+    pub fn pixel(&self, position: UVec2) -> Option<&[u8]> {
+        if position.x >= self.width || position.y >= self.height {
+            return None;
+        }
+        let bytes_per_pixel = self.frame.len() / (self.width as usize * self.height as usize);
+        let offset =
+            (position.y as usize * self.width as usize + position.x as usize) * bytes_per_pixel;
+        self.frame.get(offset..offset + bytes_per_pixel)
+    }
+
+    /// Replace the stored frame, reusing its allocation.
+    fn store(&mut self, frame: &[u8], width: u32, height: u32) {
+        self.frame.clear();
+        self.frame.extend_from_slice(frame);
+        self.width = width;
+        self.height = height;
+    }
+}
+
+/// Store each finished frame in its [`PreviousFrame`]. Runs in the [`Render`] schedule before
+/// frames are presented. Frames that did not change since they were last stored are skipped.
+#[allow(clippy::type_complexity)]
+pub fn store_previous_frames(
+    mut query: Query<(
+        Option<Ref<PixelsWrapper>>,
+        Option<Ref<PixelsBuffer>>,
+        Option<&DirtyRects>,
+        &mut PreviousFrame,
+    )>,
+) {
+    for (wrapper, buffer, dirty, mut previous) in &mut query {
+        let dirty = dirty.is_some_and(|dirty| !dirty.is_empty());
+        if let Some(wrapper) = wrapper {
+            if wrapper.is_changed() || dirty || previous.is_added() {
+                let extent = wrapper.pixels.context().texture_extent;
+                previous.store(wrapper.pixels.frame(), extent.width, extent.height);
+            }
+        } else if let Some(buffer) = buffer
+            && (buffer.is_changed() || dirty || previous.is_added())
+        {
+            previous.store(buffer.frame(), buffer.width(), buffer.height());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_replaces_frame_and_size() {
+        let mut previous = PreviousFrame::default();
+
+        previous.store(&[1; 16], 2, 2);
+        previous.store(&[2; 8], 2, 1);
+
+        assert_eq!(previous.frame(), &[2; 8]);
+        assert_eq!((previous.width(), previous.height()), (2, 1));
+    }
+
+    #[test]
+    fn unchanged_frames_are_not_stored_again() {
+        let mut world = World::new();
+        let entity = world
+            .spawn((PixelsBuffer::new(1, 1), PreviousFrame::default()))
+            .id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(store_previous_frames);
+
+        schedule.run(&mut world);
+        assert_eq!(world.get::<PreviousFrame>(entity).unwrap().frame(), &[0; 4]);
+
+        // A stale copy shows the frame was not copied again.
+        world
+            .get_mut::<PreviousFrame>(entity)
+            .unwrap()
+            .store(&[9; 4], 1, 1);
+        schedule.run(&mut world);
+        assert_eq!(world.get::<PreviousFrame>(entity).unwrap().frame(), &[9; 4]);
+
+        world
+            .get_mut::<PixelsBuffer>(entity)
+            .unwrap()
+            .frame_mut()
+            .fill(1);
+        schedule.run(&mut world);
+        assert_eq!(world.get::<PreviousFrame>(entity).unwrap().frame(), &[1; 4]);
+    }
+
+    #[test]
+    fn pixel_reads_inside_frame_only() {
+        let mut previous = PreviousFrame::default();
+        assert_eq!(previous.pixel(UVec2::ZERO), None);
+
+        previous.store(&[0, 0, 0, 0, 1, 2, 3, 4], 2, 1);
+
+        assert_eq!(previous.pixel(UVec2::new(1, 0)), Some(&[1, 2, 3, 4][..]));
+        assert_eq!(previous.pixel(UVec2::new(2, 0)), None);
+        assert_eq!(previous.pixel(UVec2::new(0, 1)), None);
+    }
+}
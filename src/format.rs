@@ -0,0 +1,619 @@
+use crate::{PixelsBuffer, PixelsOptions, PixelsWrapper};
+
+use bevy::{prelude::ReflectDefault, reflect::Reflect};
+use half::f16;
+use pixels::wgpu;
+use std::{error::Error, fmt};
+
+/// Layout of pixels written to a frame on the CPU.
+///
+/// Frames use one of the [`FrameFormat`] layouts, selected by [`PixelsOptions::pixel_format`].
+/// `Rgb565` and `Gray8` are only source layouts: [`PixelsWrapper::write_frame`] and
+/// [`convert_pixels`] expand them while copying. Helpers taking raw byte slices, such as
+/// [`IndexedFrame::resolve`](crate::IndexedFrame::resolve) and
+/// [`Viewport::frame`](crate::Viewport::frame), expect `Rgba8`. Palettes,
+/// [`DrawTarget`](crate::DrawTarget) viewports and blits and [`PixelsWrapper::upscale`] convert to
+/// the frame's format.
+#[derive(Reflect, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[reflect(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum PixelFormat {
+    /// Four bytes per pixel in red, green, blue, alpha order.
+    #[default]
+    Rgba8,
+    /// Four bytes per pixel in blue, green, red, alpha order.
+    Bgra8,
+    /// Little-endian 16-bit pixels with 5 bits red, 6 bits green and 5 bits blue. Fully opaque.
+    Rgb565,
+    /// One byte of luminance per pixel. Fully opaque.
+    Gray8,
+    /// Four little-endian half-precision floats per pixel in red, green, blue, alpha order.
+    Rgba16Float,
+}
+
+/// Layout of a window frame, selected by [`PixelsOptions::pixel_format`]. Each layout is uploaded
+/// directly to a matching GPU texture.
+#[derive(Reflect, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[reflect(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameFormat {
+    /// Four bytes per pixel in red, green, blue, alpha order.
+    #[default]
+    Rgba8,
+    /// Four bytes per pixel in blue, green, red, alpha order.
+    Bgra8,
+    /// Four little-endian half-precision floats per pixel in red, green, blue, alpha order.
+    Rgba16Float,
+}
+
+impl From<FrameFormat> for PixelFormat {
+    fn from(format: FrameFormat) -> Self {
+        match format {
+            FrameFormat::Rgba8 => PixelFormat::Rgba8,
+            FrameFormat::Bgra8 => PixelFormat::Bgra8,
+            FrameFormat::Rgba16Float => PixelFormat::Rgba16Float,
+        }
+    }
+}
+
+impl FrameFormat {
+    /// Texture format frames of this layout are uploaded to.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError::UnsupportedTextureColorSpace`] for `Rgba16Float` with
+    /// [`ColorSpace::Srgb`], which has no matching texture format.
+    pub fn texture_format(
+        self,
+        color_space: ColorSpace,
+    ) -> Result<wgpu::TextureFormat, FormatError> {
+        Ok(match (self, color_space) {
+            (FrameFormat::Rgba8, ColorSpace::Linear) => wgpu::TextureFormat::Rgba8Unorm,
+            (FrameFormat::Rgba8, ColorSpace::Srgb) => wgpu::TextureFormat::Rgba8UnormSrgb,
+            (FrameFormat::Bgra8, ColorSpace::Linear) => wgpu::TextureFormat::Bgra8Unorm,
+            (FrameFormat::Bgra8, ColorSpace::Srgb) => wgpu::TextureFormat::Bgra8UnormSrgb,
+            (FrameFormat::Rgba16Float, ColorSpace::Linear) => wgpu::TextureFormat::Rgba16Float,
+            (FrameFormat::Rgba16Float, ColorSpace::Srgb) => {
+                return Err(FormatError::UnsupportedTextureColorSpace(self.into()));
+            }
+        })
+    }
+}
+
+impl PixelFormat {
+    /// Number of bytes used by a single pixel.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 => 4,
+            PixelFormat::Rgb565 => 2,
+            PixelFormat::Gray8 => 1,
+            PixelFormat::Rgba16Float => 8,
+        }
+    }
+
+    /// Pixel format of a frame uploaded to `texture_format`, if supported.
+    pub fn from_texture_format(texture_format: wgpu::TextureFormat) -> Option<Self> {
+        match texture_format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {
+                Some(PixelFormat::Rgba8)
+            }
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+                Some(PixelFormat::Bgra8)
+            }
+            wgpu::TextureFormat::Rgba16Float => Some(PixelFormat::Rgba16Float),
+            _ => None,
+        }
+    }
+
+    fn read(self, pixel: &[u8]) -> [u8; 4] {
+        match self {
+            PixelFormat::Rgba8 => [pixel[0], pixel[1], pixel[2], pixel[3]],
+            PixelFormat::Bgra8 => [pixel[2], pixel[1], pixel[0], pixel[3]],
+            PixelFormat::Rgb565 => {
+                let value = u16::from_le_bytes([pixel[0], pixel[1]]);
+                let expand = |bits: u16, max: u16| {
+                    ((u32::from(bits) * 255 + u32::from(max) / 2) / u32::from(max)) as u8
+                };
+                [
+                    expand(value >> 11, 0x1f),
+                    expand((value >> 5) & 0x3f, 0x3f),
+                    expand(value & 0x1f, 0x1f),
+                    255,
+                ]
+            }
+            PixelFormat::Gray8 => [pixel[0], pixel[0], pixel[0], 255],
+            PixelFormat::Rgba16Float => std::array::from_fn(|channel| {
+                let offset = channel * 2;
+                let value = f16::from_le_bytes([pixel[offset], pixel[offset + 1]]).to_f32();
+                (value.clamp(0.0, 1.0) * 255.0).round() as u8
+            }),
+        }
+    }
+
+    pub(crate) fn write(self, [r, g, b, a]: [u8; 4], pixel: &mut [u8]) {
+        match self {
+            PixelFormat::Rgba8 => pixel.copy_from_slice(&[r, g, b, a]),
+            PixelFormat::Bgra8 => pixel.copy_from_slice(&[b, g, r, a]),
+            PixelFormat::Rgb565 => {
+                let value =
+                    (u16::from(r) >> 3) << 11 | (u16::from(g) >> 2) << 5 | u16::from(b) >> 3;
+                pixel.copy_from_slice(&value.to_le_bytes());
+            }
+            PixelFormat::Gray8 => {
+                // Rec. 601 luma.
+                let luma =
+                    (299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b) + 500) / 1000;
+                pixel[0] = luma as u8;
+            }
+            PixelFormat::Rgba16Float => {
+                for (channel, value) in pixel.chunks_exact_mut(2).zip([r, g, b, a]) {
+                    let value = f16::from_f32(f32::from(value) / 255.0);
+                    channel.copy_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+    }
+}
+
+/// Convert pixels from `source` in `from` format into `target` in `to` format.
+///
+/// Identical formats are copied and `Rgba8`/`Bgra8` are swizzled in place. Other conversions go
+/// through 8-bit RGBA, so converting between `Rgba16Float` frames of different formats clamps
+/// values to `0.0..=1.0`.
+///
+/// # Errors
+///
+/// Returns [`FormatError::FrameSize`] when `target` does not hold exactly as many pixels as
+/// `source`.
+pub fn convert_pixels(
+    source: &[u8],
+    from: PixelFormat,
+    target: &mut [u8],
+    to: PixelFormat,
+) -> Result<(), FormatError> {
+    let pixels = source.len() / from.bytes_per_pixel();
+    let expected = pixels * to.bytes_per_pixel();
+    if !source.len().is_multiple_of(from.bytes_per_pixel()) || target.len() != expected {
+        return Err(FormatError::FrameSize {
+            expected,
+            actual: target.len(),
+        });
+    }
+
+    match (from, to) {
+        _ if from == to => target.copy_from_slice(source),
+        (PixelFormat::Rgba8, PixelFormat::Bgra8) | (PixelFormat::Bgra8, PixelFormat::Rgba8) => {
+            for (source, target) in source.chunks_exact(4).zip(target.chunks_exact_mut(4)) {
+                target.copy_from_slice(&[source[2], source[1], source[0], source[3]]);
+            }
+        }
+        _ => {
+            for (source, target) in source
+                .chunks_exact(from.bytes_per_pixel())
+                .zip(target.chunks_exact_mut(to.bytes_per_pixel()))
+            {
+                to.write(from.read(source), target);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl PixelsWrapper {
+    /// Pixel format of the frame, determined by the texture format it is uploaded to.
+    pub fn pixel_format(&self) -> Option<PixelFormat> {
+        PixelFormat::from_texture_format(self.pixels.context().texture_format)
+    }
+
+    /// Write a whole frame of `format` pixels, converting them to the frame's pixel format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError`] when the frame has an unsupported texture format or `source` does
+    /// not hold exactly one frame of pixels.
+    pub fn write_frame(&mut self, source: &[u8], format: PixelFormat) -> Result<(), FormatError> {
+        let context = self.pixels.context();
+        let to = PixelFormat::from_texture_format(context.texture_format).ok_or(
+            FormatError::UnsupportedTextureFormat(context.texture_format),
+        )?;
+        let extent = context.texture_extent;
+        check_frame_size(source, format, extent.width, extent.height)?;
+        convert_pixels(source, format, self.pixels.frame_mut(), to)
+    }
+}
+
+impl PixelsBuffer {
+    /// Write a whole frame of `format` pixels, converting them to RGBA8.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError::FrameSize`] when `source` does not hold exactly one frame of pixels.
+    pub fn write_frame(&mut self, source: &[u8], format: PixelFormat) -> Result<(), FormatError> {
+        check_frame_size(source, format, self.width(), self.height())?;
+        convert_pixels(source, format, self.frame_mut(), PixelFormat::Rgba8)
+    }
+}
+
+pub(crate) fn check_frame_size(
+    source: &[u8],
+    format: PixelFormat,
+    width: u32,
+    height: u32,
+) -> Result<(), FormatError> {
+    let expected = width as usize * height as usize * format.bytes_per_pixel();
+    if source.len() == expected {
+        Ok(())
+    } else {
+        Err(FormatError::FrameSize {
+            expected,
+            actual: source.len(),
+        })
+    }
+}
+
+/// Color space of a texture used by the pixel buffer.
+///
+/// When the buffer and surface color spaces match, buffer bytes are displayed unchanged on every
+/// platform. `Srgb` additionally makes the GPU convert to linear light when sampling, so scaling
+/// filters and blending are gamma-correct. Mixing color spaces intentionally brightens (`Linear`
+/// buffer on an `Srgb` surface) or darkens (`Srgb` buffer on a `Linear` surface) the output.
+///
+/// Without a color space in [`PixelsOptions`], the `pixels` defaults are used: `Srgb` on native
+/// platforms and `Linear` on the web. `Rgba16Float` frames hold linear light and default to a
+/// `Linear` texture.
+#[derive(Reflect, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[reflect(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorSpace {
+    /// Values are stored and displayed without conversion (`Unorm` formats). Supported for every
+    /// texture and on web surfaces; native surfaces without a `Unorm` format reject it.
+    #[default]
+    Linear,
+    /// Values are sRGB encoded (`UnormSrgb` formats). Not supported for web surfaces.
+    Srgb,
+}
+
+/// `wgpu` texture formats selected for a pixel buffer by [`PixelsOptions::texture_formats`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TextureFormats {
+    /// Format of the buffer texture the frame is uploaded to.
+    pub texture: wgpu::TextureFormat,
+    /// Format of the window surface. `None` lets the surface choose its preferred sRGB format.
+    pub surface: Option<wgpu::TextureFormat>,
+    /// Whether the surface must not be sRGB encoded. Native surfaces then use the first such
+    /// format they report.
+    pub linear_surface: bool,
+}
+
+/// Unsupported texture format configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FormatError {
+    /// sRGB surfaces are not available on the current platform.
+    UnsupportedSurfaceColorSpace(ColorSpace),
+    /// The pixel format has no texture format in the requested color space.
+    UnsupportedTextureColorSpace(PixelFormat),
+    /// The texture format has no matching [`PixelFormat`].
+    UnsupportedTextureFormat(wgpu::TextureFormat),
+    /// The operation only supports [`PixelFormat::Rgba8`] frames.
+    UnsupportedPixelFormat(PixelFormat),
+    /// A frame has the wrong length in bytes.
+    FrameSize { expected: usize, actual: usize },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::UnsupportedSurfaceColorSpace(ColorSpace::Srgb) => write!(
+                f,
+                "surface color space Srgb is not supported on this platform, use \
+                 `ColorSpace::Linear` instead"
+            ),
+            FormatError::UnsupportedSurfaceColorSpace(ColorSpace::Linear) => write!(
+                f,
+                "surface color space Linear is not supported by this window, use \
+                 `ColorSpace::Srgb` instead"
+            ),
+            FormatError::UnsupportedTextureColorSpace(format) => write!(
+                f,
+                "pixel format {format:?} has no sRGB texture format, use `ColorSpace::Linear` \
+                 instead"
+            ),
+            FormatError::UnsupportedTextureFormat(format) => {
+                write!(f, "texture format {format:?} has no matching pixel format")
+            }
+            FormatError::UnsupportedPixelFormat(format) => write!(
+                f,
+                "pixel format {format:?} is not supported here, only `PixelFormat::Rgba8` is; \
+                 use `par_rows_mut` for other formats"
+            ),
+            FormatError::FrameSize { expected, actual } => {
+                write!(f, "expected a frame of {expected} bytes, got {actual}")
+            }
+        }
+    }
+}
+
+impl Error for FormatError {}
+
+impl PixelsOptions {
+    /// Select the `wgpu` texture formats for these options on the current platform.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError`] when the requested combination is not supported on this platform.
+    pub fn texture_formats(&self) -> Result<TextureFormats, FormatError> {
+        texture_formats(self, cfg!(target_arch = "wasm32"))
+    }
+}
+
+/// First format of a window surface with `supported` formats that is not sRGB encoded.
+pub(crate) fn linear_surface_format(
+    supported: &[wgpu::TextureFormat],
+) -> Result<wgpu::TextureFormat, FormatError> {
+    supported
+        .iter()
+        .copied()
+        .find(|format| !format.is_srgb())
+        .ok_or(FormatError::UnsupportedSurfaceColorSpace(
+            ColorSpace::Linear,
+        ))
+}
+
+fn texture_formats(options: &PixelsOptions, web: bool) -> Result<TextureFormats, FormatError> {
+    let texture = match options.texture_color_space {
+        Some(color_space) => options.pixel_format.texture_format(color_space)?,
+        None if web => options.pixel_format.texture_format(ColorSpace::Linear)?,
+        None => options
+            .pixel_format
+            .texture_format(ColorSpace::Srgb)
+            .or_else(|_| options.pixel_format.texture_format(ColorSpace::Linear))?,
+    };
+    let (surface, linear_surface) = match (options.surface_color_space, web) {
+        (None | Some(ColorSpace::Linear), true) => (Some(wgpu::TextureFormat::Rgba8Unorm), true),
+        (Some(ColorSpace::Srgb), true) => {
+            return Err(FormatError::UnsupportedSurfaceColorSpace(ColorSpace::Srgb));
+        }
+        (Some(ColorSpace::Linear), false) => (None, true),
+        (None | Some(ColorSpace::Srgb), false) => (None, false),
+    };
+
+    Ok(TextureFormats {
+        texture,
+        surface,
+        linear_surface,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(texture: ColorSpace, surface: ColorSpace) -> PixelsOptions {
+        PixelsOptions {
+            texture_color_space: Some(texture),
+            surface_color_space: Some(surface),
+            ..PixelsOptions::default()
+        }
+    }
+
+    #[test]
+    fn default_formats_match_pixels_defaults() {
+        let options = PixelsOptions::default();
+
+        assert_eq!(
+            texture_formats(&options, false),
+            Ok(TextureFormats {
+                texture: wgpu::TextureFormat::Rgba8UnormSrgb,
+                surface: None,
+                linear_surface: false,
+            })
+        );
+        assert_eq!(
+            texture_formats(&options, true),
+            Ok(TextureFormats {
+                texture: wgpu::TextureFormat::Rgba8Unorm,
+                surface: Some(wgpu::TextureFormat::Rgba8Unorm),
+                linear_surface: true,
+            })
+        );
+    }
+
+    #[test]
+    fn default_float_texture_is_linear() {
+        let options = PixelsOptions {
+            pixel_format: FrameFormat::Rgba16Float,
+            ..PixelsOptions::default()
+        };
+
+        assert_eq!(
+            texture_formats(&options, false).map(|formats| formats.texture),
+            Ok(wgpu::TextureFormat::Rgba16Float)
+        );
+    }
+
+    #[test]
+    fn linear_native_surface_is_chosen_from_capabilities() {
+        assert_eq!(
+            texture_formats(&options(ColorSpace::Linear, ColorSpace::Linear), false),
+            Ok(TextureFormats {
+                texture: wgpu::TextureFormat::Rgba8Unorm,
+                surface: None,
+                linear_surface: true,
+            })
+        );
+    }
+
+    #[test]
+    fn srgb_formats_use_preferred_native_surface() {
+        assert_eq!(
+            texture_formats(&options(ColorSpace::Srgb, ColorSpace::Srgb), false),
+            Ok(TextureFormats {
+                texture: wgpu::TextureFormat::Rgba8UnormSrgb,
+                surface: None,
+                linear_surface: false,
+            })
+        );
+    }
+
+    #[test]
+    fn srgb_texture_is_supported_on_web() {
+        assert_eq!(
+            texture_formats(&options(ColorSpace::Srgb, ColorSpace::Linear), true)
+                .map(|formats| formats.texture),
+            Ok(wgpu::TextureFormat::Rgba8UnormSrgb)
+        );
+    }
+
+    #[test]
+    fn linear_surface_format_is_first_non_srgb_format() {
+        let supported = [
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+            wgpu::TextureFormat::Rgb10a2Unorm,
+            wgpu::TextureFormat::Bgra8Unorm,
+        ];
+
+        assert_eq!(
+            linear_surface_format(&supported),
+            Ok(wgpu::TextureFormat::Rgb10a2Unorm)
+        );
+        assert_eq!(
+            linear_surface_format(&[wgpu::TextureFormat::Bgra8UnormSrgb]),
+            Err(FormatError::UnsupportedSurfaceColorSpace(
+                ColorSpace::Linear
+            ))
+        );
+    }
+
+    #[test]
+    fn pixel_formats_select_direct_upload_textures() {
+        let formats = |format| {
+            texture_formats(
+                &PixelsOptions {
+                    pixel_format: format,
+                    texture_color_space: Some(ColorSpace::Linear),
+                    ..PixelsOptions::default()
+                },
+                false,
+            )
+            .map(|formats| formats.texture)
+        };
+
+        assert_eq!(
+            formats(FrameFormat::Rgba8),
+            Ok(wgpu::TextureFormat::Rgba8Unorm)
+        );
+        assert_eq!(
+            formats(FrameFormat::Bgra8),
+            Ok(wgpu::TextureFormat::Bgra8Unorm)
+        );
+        assert_eq!(
+            formats(FrameFormat::Rgba16Float),
+            Ok(wgpu::TextureFormat::Rgba16Float)
+        );
+        assert_eq!(
+            FrameFormat::Rgba16Float.texture_format(ColorSpace::Srgb),
+            Err(FormatError::UnsupportedTextureColorSpace(
+                PixelFormat::Rgba16Float
+            ))
+        );
+    }
+
+    #[test]
+    fn convert_expands_packed_formats_to_rgba8() {
+        let rgb565 = [0xf800_u16, 0x07e0, 0x001f, 0xffff]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        let mut rgba = [0; 16];
+
+        convert_pixels(&rgb565, PixelFormat::Rgb565, &mut rgba, PixelFormat::Rgba8).unwrap();
+        assert_eq!(
+            rgba,
+            [
+                255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255
+            ]
+        );
+
+        convert_pixels(
+            &[0, 128],
+            PixelFormat::Gray8,
+            &mut rgba[..8],
+            PixelFormat::Rgba8,
+        )
+        .unwrap();
+        assert_eq!(rgba[..8], [0, 0, 0, 255, 128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn convert_swizzles_and_round_trips_float() {
+        let rgba = [10, 20, 30, 40, 255, 0, 128, 255];
+        let mut bgra = [0; 8];
+        convert_pixels(&rgba, PixelFormat::Rgba8, &mut bgra, PixelFormat::Bgra8).unwrap();
+        assert_eq!(bgra, [30, 20, 10, 40, 128, 0, 255, 255]);
+
+        let mut float = [0; 16];
+        let mut back = [0; 8];
+        convert_pixels(
+            &rgba,
+            PixelFormat::Rgba8,
+            &mut float,
+            PixelFormat::Rgba16Float,
+        )
+        .unwrap();
+        assert_eq!(float[8..10], f16::ONE.to_le_bytes());
+        convert_pixels(
+            &float,
+            PixelFormat::Rgba16Float,
+            &mut back,
+            PixelFormat::Rgba8,
+        )
+        .unwrap();
+        assert_eq!(back, rgba);
+    }
+
+    #[test]
+    fn convert_rejects_mismatched_frames() {
+        let mut target = [0; 4];
+
+        assert_eq!(
+            convert_pixels(&[0; 4], PixelFormat::Gray8, &mut target, PixelFormat::Rgba8),
+            Err(FormatError::FrameSize {
+                expected: 16,
+                actual: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn buffer_write_frame_checks_source_size() {
+        let mut buffer = PixelsBuffer::new(2, 1);
+
+        buffer.write_frame(&[255, 0], PixelFormat::Gray8).unwrap();
+        assert_eq!(buffer.frame(), &[255, 255, 255, 255, 0, 0, 0, 255]);
+        assert_eq!(
+            buffer.write_frame(&[0; 3], PixelFormat::Gray8),
+            Err(FormatError::FrameSize {
+                expected: 2,
+                actual: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn srgb_surface_is_rejected_on_web() {
+        let error = texture_formats(&options(ColorSpace::Linear, ColorSpace::Srgb), true);
+
+        assert_eq!(
+            error,
+            Err(FormatError::UnsupportedSurfaceColorSpace(ColorSpace::Srgb))
+        );
+        assert!(
+            error
+                .unwrap_err()
+                .to_string()
+                .contains("ColorSpace::Linear")
+        );
+    }
+}
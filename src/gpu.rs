@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use pixels::wgpu;
+
+/// GPU adapter and device options used when creating window pixel buffers.
+///
+/// Set through [`PixelsPlugin::gpu`](crate::PixelsPlugin::gpu) and stored as a resource. For
+/// field debugging, the standard wgpu environment variables override the configured values:
+/// `WGPU_BACKEND` (for example `vulkan,gl`) replaces `backends`, `WGPU_POWER_PREF` (`low`, `high`
+/// or `none`) replaces `power_preference` and `WGPU_ADAPTER_NAME` selects an adapter by name.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct GpuOptions {
+    /// Backends wgpu may use.
+    pub backends: wgpu::Backends,
+    /// Preferred adapter power profile.
+    pub power_preference: wgpu::PowerPreference,
+    /// Only request a software fallback adapter.
+    pub force_fallback_adapter: bool,
+    /// Device limits to require. `None` requests the limits supported by the adapter.
+    pub required_limits: Option<wgpu::Limits>,
+}
+
+impl Default for GpuOptions {
+    fn default() -> Self {
+        GpuOptions {
+            backends: wgpu::Backends::all(),
+            power_preference: wgpu::PowerPreference::None,
+            force_fallback_adapter: false,
+            required_limits: None,
+        }
+    }
+}
+
+impl GpuOptions {
+    /// Apply the `WGPU_BACKEND` and `WGPU_POWER_PREF` environment variable overrides.
+    #[must_use]
+    pub fn with_env(self) -> Self {
+        self.with_overrides(
+            wgpu::Backends::from_env(),
+            wgpu::PowerPreference::from_env(),
+        )
+    }
+
+    fn with_overrides(
+        self,
+        backends: Option<wgpu::Backends>,
+        power_preference: Option<wgpu::PowerPreference>,
+    ) -> Self {
+        GpuOptions {
+            backends: backends.unwrap_or(self.backends),
+            power_preference: power_preference.unwrap_or(self.power_preference),
+            ..self
+        }
+    }
+
+    pub(crate) fn request_adapter_options(&self) -> wgpu::RequestAdapterOptions<'static, 'static> {
+        wgpu::RequestAdapterOptions {
+            power_preference: self.power_preference,
+            force_fallback_adapter: self.force_fallback_adapter,
+            compatible_surface: None,
+        }
+    }
+
+    pub(crate) fn device_descriptor(&self) -> Option<wgpu::DeviceDescriptor<'static>> {
+        self.required_limits
+            .clone()
+            .map(|required_limits| wgpu::DeviceDescriptor {
+                required_limits,
+                ..default()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environment_overrides_replace_configured_values() {
+        let options = GpuOptions {
+            backends: wgpu::Backends::VULKAN,
+            power_preference: wgpu::PowerPreference::LowPower,
+            force_fallback_adapter: true,
+            required_limits: None,
+        };
+
+        let overridden = options.clone().with_overrides(
+            Some(wgpu::Backends::GL),
+            Some(wgpu::PowerPreference::HighPerformance),
+        );
+
+        assert_eq!(overridden.backends, wgpu::Backends::GL);
+        assert_eq!(
+            overridden.power_preference,
+            wgpu::PowerPreference::HighPerformance
+        );
+        assert!(overridden.force_fallback_adapter);
+        assert_eq!(options.clone().with_overrides(None, None), options);
+    }
+
+    #[test]
+    fn device_descriptor_is_only_set_for_required_limits() {
+        assert!(GpuOptions::default().device_descriptor().is_none());
+
+        let options = GpuOptions {
+            required_limits: Some(wgpu::Limits::downlevel_webgl2_defaults()),
+            ..default()
+        };
+        assert_eq!(
+            options
+                .device_descriptor()
+                .map(|descriptor| descriptor.required_limits),
+            Some(wgpu::Limits::downlevel_webgl2_defaults())
+        );
+    }
+}
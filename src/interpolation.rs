@@ -0,0 +1,140 @@
+use bevy::{prelude::*, time::Fixed};
+
+/// Interpolation between the last two fixed timesteps, for drawing state simulated in
+/// [`FixedUpdate`] once per frame in [`Draw`](crate::schedule::Draw).
+///
+/// The plugin updates [`alpha`](Self::alpha) every frame in [`PostUpdate`]. It stays `1.0` (the
+/// latest fixed state) without a [`Time<Fixed>`] resource.
+#[derive(Resource, Reflect, Debug, Copy, Clone, PartialEq)]
+#[reflect(Resource, Debug, Default, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct FixedInterpolation {
+    /// Fraction of a fixed timestep accumulated since the last fixed update, in `0.0..1.0`.
+    pub alpha: f32,
+    /// Round interpolated positions to whole pixels, avoiding shimmering at low resolutions.
+    pub snap: bool,
+}
+
+impl Default for FixedInterpolation {
+    fn default() -> Self {
+        FixedInterpolation {
+            alpha: 1.0,
+            snap: false,
+        }
+    }
+}
+
+impl FixedInterpolation {
+    /// Interpolated `position` for this frame, rounded to whole pixels when
+    /// [`snap`](Self::snap) is set.
+    pub fn position(&self, position: &InterpolatedPosition) -> Vec2 {
+        let interpolated = position.lerp(self.alpha);
+        if self.snap {
+            interpolated.round()
+        } else {
+            interpolated
+        }
+    }
+}
+
+/// Position of an entity at the previous and current fixed timestep.
+///
+/// Update it with [`set`](Self::set) from [`FixedUpdate`] systems and draw it with
+/// [`FixedInterpolation::position`].
+#[derive(Component, Reflect, Debug, Default, Copy, Clone, PartialEq)]
+#[reflect(Component, Debug, Default, PartialEq, Clone)]
+pub struct InterpolatedPosition {
+    previous: Vec2,
+    current: Vec2,
+}
+
+impl InterpolatedPosition {
+    /// Start at `position` without interpolation.
+    pub fn new(position: Vec2) -> Self {
+        InterpolatedPosition {
+            previous: position,
+            current: position,
+        }
+    }
+
+    /// Move to `position`, keeping the current position as the previous one.
+    pub fn set(&mut self, position: Vec2) {
+        self.previous = self.current;
+        self.current = position;
+    }
+
+    /// Jump to `position` without interpolating from the previous one.
+    pub fn teleport(&mut self, position: Vec2) {
+        *self = InterpolatedPosition::new(position);
+    }
+
+    /// Position at the previous fixed timestep.
+    pub fn previous(&self) -> Vec2 {
+        self.previous
+    }
+
+    /// Position at the current fixed timestep.
+    pub fn current(&self) -> Vec2 {
+        self.current
+    }
+
+    /// Position `alpha` of the way from the previous to the current position.
+    pub fn lerp(&self, alpha: f32) -> Vec2 {
+        self.previous.lerp(self.current, alpha)
+    }
+
+    /// Position `alpha` of the way from the previous to the current position, rounded to the
+    /// nearest pixel.
+    pub fn pixel(&self, alpha: f32) -> IVec2 {
+        self.lerp(alpha).round().as_ivec2()
+    }
+}
+
+/// Copy the fixed timestep overstep fraction into [`FixedInterpolation`].
+pub(crate) fn update_fixed_interpolation(
+    time: Option<Res<Time<Fixed>>>,
+    mut interpolation: ResMut<FixedInterpolation>,
+) {
+    if let Some(time) = time {
+        interpolation.alpha = time.overstep_fraction();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_keeps_previous_position() {
+        let mut position = InterpolatedPosition::new(Vec2::new(1.0, 2.0));
+
+        position.set(Vec2::new(3.0, 6.0));
+
+        assert_eq!(position.previous(), Vec2::new(1.0, 2.0));
+        assert_eq!(position.current(), Vec2::new(3.0, 6.0));
+        assert_eq!(position.lerp(0.5), Vec2::new(2.0, 4.0));
+
+        position.teleport(Vec2::ZERO);
+        assert_eq!(position.lerp(0.5), Vec2::ZERO);
+    }
+
+    #[test]
+    fn snapping_rounds_to_whole_pixels() {
+        let mut position = InterpolatedPosition::new(Vec2::ZERO);
+        position.set(Vec2::new(3.0, -3.0));
+        let mut interpolation = FixedInterpolation {
+            alpha: 0.4,
+            snap: false,
+        };
+
+        assert!((interpolation.position(&position) - Vec2::new(1.2, -1.2)).length() < 1e-6);
+
+        interpolation.snap = true;
+        assert_eq!(interpolation.position(&position), Vec2::new(1.0, -1.0));
+        assert_eq!(position.pixel(0.5), IVec2::new(2, -2));
+    }
+}
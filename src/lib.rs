@@ -1,15 +1,48 @@
 pub mod diagnostic;
+pub mod pipelined;
 pub mod prelude;
 pub mod schedule;
 
+mod buffer;
+mod crt;
+mod dirty;
+mod double_buffer;
+mod format;
+mod gpu;
+mod interpolation;
+mod mirror;
 mod options;
+mod palette;
+mod palette_format;
+mod parallel;
 mod plugin;
+mod post_process;
+mod redraw;
 mod synchronization;
 mod system;
+mod target;
+mod upscale;
+mod viewport;
 mod wrapper;
 
+pub use buffer::*;
+pub use crt::*;
+pub use dirty::*;
+pub use double_buffer::*;
+pub use format::*;
+pub use gpu::*;
+pub use interpolation::*;
+pub use mirror::*;
 pub use options::*;
+pub use palette::*;
+pub use palette_format::*;
+pub use parallel::*;
 pub use plugin::*;
+pub use post_process::*;
+pub use redraw::*;
+pub use target::*;
+pub use upscale::*;
+pub use viewport::*;
 pub use wrapper::*;
 
 pub use pixels;
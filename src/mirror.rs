@@ -0,0 +1,90 @@
+use crate::prelude::*;
+
+use bevy::{log::warn, prelude::*};
+
+/// Display the pixel buffer of another entity in this window. The source can be another window
+/// with a [`PixelsWrapper`] or an offscreen [`PixelsBuffer`].
+///
+/// The mirror window keeps its own surface, so it can use its own
+/// [`scaling_mode`](PixelsOptions::scaling_mode). The mirror buffer always matches the source
+/// buffer size and `auto_resize_buffer` is ignored. Frames are converted when the mirror uses a
+/// different [`pixel_format`](PixelsOptions::pixel_format).
+#[derive(Component, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PixelsSource(pub Entity);
+
+/// Copy each [`PixelsSource`] buffer into its mirror window buffer. Runs in the [`Render`]
+/// schedule before the default render system. Unchanged sources are not copied again, so static
+/// mirrors are not presented every frame.
+pub fn mirror_sources(
+    mut mirrors: Query<(Entity, Ref<PixelsSource>, &mut PixelsOptions)>,
+    mut wrappers: Query<&mut PixelsWrapper>,
+    buffers: Query<Ref<PixelsBuffer>>,
+) {
+    for (entity, source, mut options) in &mut mirrors {
+        let outdated = |source_changed: bool, wrapper: &Mut<PixelsWrapper>, size: (u32, u32)| {
+            source_changed
+                || source.is_changed()
+                || wrapper.is_added()
+                || (options.width, options.height) != size
+        };
+
+        if let Ok([source_wrapper, mut wrapper]) = wrappers.get_many_mut([source.0, entity]) {
+            let extent = source_wrapper.pixels.context().texture_extent;
+            let size = (extent.width, extent.height);
+            let Some(format) = source_wrapper.pixel_format() else {
+                continue;
+            };
+            if !outdated(source_wrapper.is_changed(), &wrapper, size) {
+                continue;
+            }
+            copy_frame(
+                entity,
+                source_wrapper.pixels.frame(),
+                format,
+                size,
+                &mut wrapper,
+                &mut options,
+            );
+        } else if let (Ok(buffer), Ok(mut wrapper)) =
+            (buffers.get(source.0), wrappers.get_mut(entity))
+        {
+            let size = (buffer.width(), buffer.height());
+            if !outdated(buffer.is_changed(), &wrapper, size) {
+                continue;
+            }
+            copy_frame(
+                entity,
+                buffer.frame(),
+                PixelFormat::Rgba8,
+                size,
+                &mut wrapper,
+                &mut options,
+            );
+        }
+    }
+}
+
+fn copy_frame(
+    entity: Entity,
+    frame: &[u8],
+    format: PixelFormat,
+    (width, height): (u32, u32),
+    wrapper: &mut PixelsWrapper,
+    options: &mut Mut<PixelsOptions>,
+) {
+    if (options.width, options.height) != (width, height) {
+        // Resize immediately so this frame can be copied. Change detection is bypassed because
+        // the buffer already matches the new options.
+        if let Err(error) = wrapper.pixels.resize_buffer(width, height) {
+            warn!(?entity, %error, "failed to resize mirrored pixel buffer");
+            return;
+        }
+        let options = options.bypass_change_detection();
+        options.width = width;
+        options.height = height;
+    }
+
+    if let Err(error) = wrapper.write_frame(frame, format) {
+        warn!(?entity, %error, "failed to copy mirrored pixel buffer");
+    }
+}
@@ -1,7 +1,17 @@
+use crate::{ColorSpace, FrameFormat};
+
 use bevy::prelude::*;
+use pixels::wgpu;
+use std::{error::Error, fmt};
 
 /// Defines the sizing and behavior of the pixel buffer and surface texture.
-#[derive(Component, Debug, Copy, Clone, PartialEq)]
+#[derive(Component, Reflect, Debug, Copy, Clone, PartialEq)]
+#[reflect(Component, Debug, Default, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct PixelsOptions {
     /// Width of the pixel buffer. Changing this after initialization will resize the buffer.
     pub width: u32,
@@ -10,10 +20,49 @@ pub struct PixelsOptions {
     /// Scale factor between logical window size and buffer size. Only used when
     /// `auto_resize_buffer` is enabled.
     pub scale_factor: f32,
+    /// Smallest `(width, height)` of automatically sized buffers. The scale factor is reduced
+    /// when a small window would give a smaller buffer.
+    pub min_size: Option<(u32, u32)>,
+    /// Largest `(width, height)` of automatically sized buffers. The scale factor is increased
+    /// when a large window would give a larger buffer, so fill-rate stays bounded.
+    pub max_size: Option<(u32, u32)>,
     /// Should the buffer automatically be resized when the window changes?
     pub auto_resize_buffer: bool,
     /// Should the surface texture automatically be resized when the window changes?
     pub auto_resize_surface: bool,
+    /// How the buffer is scaled to the window surface.
+    pub scaling_mode: ScalingMode,
+    /// Layout of pixels in the CPU frame. Other [`PixelFormat`](crate::PixelFormat)s can be
+    /// written with [`PixelsWrapper::write_frame`](crate::PixelsWrapper::write_frame). Only used
+    /// when the buffer is created.
+    pub pixel_format: FrameFormat,
+    /// Color space of the buffer texture, or `None` for the platform default. See [`ColorSpace`]
+    /// for how it interacts with `surface_color_space`. Only used when the buffer is created.
+    pub texture_color_space: Option<ColorSpace>,
+    /// Color space of the window surface, or `None` for the platform default. Only used when the
+    /// buffer is created.
+    pub surface_color_space: Option<ColorSpace>,
+}
+
+/// Controls how the pixel buffer is scaled to the window surface.
+#[derive(Reflect, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[reflect(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScalingMode {
+    /// Scale up to the largest integer multiple of the buffer size that fits.
+    #[default]
+    PixelPerfect,
+    /// Fill the surface while preserving aspect ratio.
+    Fill,
+}
+
+impl From<ScalingMode> for pixels::ScalingMode {
+    fn from(scaling_mode: ScalingMode) -> Self {
+        match scaling_mode {
+            ScalingMode::PixelPerfect => pixels::ScalingMode::PixelPerfect,
+            ScalingMode::Fill => pixels::ScalingMode::Fill,
+        }
+    }
 }
 
 impl Default for PixelsOptions {
@@ -22,12 +71,159 @@ impl Default for PixelsOptions {
             width: 1280,
             height: 720,
             scale_factor: 1.0,
+            min_size: None,
+            max_size: None,
             auto_resize_buffer: true,
             auto_resize_surface: true,
+            scaling_mode: ScalingMode::PixelPerfect,
+            pixel_format: FrameFormat::Rgba8,
+            texture_color_space: None,
+            surface_color_space: None,
+        }
+    }
+}
+
+impl PixelsOptions {
+    /// Create default options with a `width` x `height` buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OptionsError::ZeroSize`] when `width` or `height` is zero.
+    pub fn new(width: u32, height: u32) -> Result<Self, OptionsError> {
+        PixelsOptions {
+            width,
+            height,
+            ..default()
+        }
+        .validated()
+    }
+
+    /// Return these options if they are valid, for checking options built with struct update
+    /// syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OptionsError`] as described by [`validate`](Self::validate).
+    pub fn validated(self) -> Result<Self, OptionsError> {
+        self.validate().map(|()| self)
+    }
+
+    /// Check the options that do not depend on the GPU.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OptionsError::ZeroSize`] when `width` or `height` is zero,
+    /// [`OptionsError::InvalidScaleFactor`] when `scale_factor` is not a finite positive number
+    /// and [`OptionsError::InvalidSizeLimits`] when `min_size` or `max_size` is empty or
+    /// `min_size` exceeds `max_size`.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        self.validate_with_max_dimension(None)
+    }
+
+    /// Check the options against the texture size `limits` of a GPU device.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OptionsError`] as described by [`validate`](Self::validate), and
+    /// [`OptionsError::SizeTooLarge`] when the buffer exceeds `max_texture_dimension_2d`.
+    pub fn validate_for_limits(&self, limits: &wgpu::Limits) -> Result<(), OptionsError> {
+        self.validate_with_max_dimension(Some(limits.max_texture_dimension_2d))
+    }
+
+    /// Scale factor used to size the buffer of a window with `logical_size` when
+    /// `auto_resize_buffer` is enabled, after applying [`min_size`](Self::min_size) and
+    /// [`max_size`](Self::max_size). The minimum wins when both cannot be met.
+    pub fn effective_scale_factor(&self, logical_size: Vec2) -> f32 {
+        let mut scale_factor = self.scale_factor;
+        if let Some((width, height)) = self.max_size {
+            scale_factor = scale_factor
+                .max(logical_size.x / width as f32)
+                .max(logical_size.y / height as f32);
+        }
+        if let Some((width, height)) = self.min_size {
+            scale_factor = scale_factor
+                .min(logical_size.x / width as f32)
+                .min(logical_size.y / height as f32);
+        }
+        scale_factor
+    }
+
+    pub(crate) fn validate_with_max_dimension(
+        &self,
+        max_dimension: Option<u32>,
+    ) -> Result<(), OptionsError> {
+        let (width, height) = (self.width, self.height);
+        if width == 0 || height == 0 {
+            return Err(OptionsError::ZeroSize { width, height });
+        }
+        if !self.scale_factor.is_finite() || self.scale_factor <= 0.0 {
+            return Err(OptionsError::InvalidScaleFactor(self.scale_factor));
+        }
+        let limits_valid = match (self.min_size, self.max_size) {
+            (Some((0, _) | (_, 0)), _) | (_, Some((0, _) | (_, 0))) => false,
+            (Some(min), Some(max)) => min.0 <= max.0 && min.1 <= max.1,
+            _ => true,
+        };
+        if !limits_valid {
+            return Err(OptionsError::InvalidSizeLimits {
+                min: self.min_size,
+                max: self.max_size,
+            });
+        }
+        match max_dimension {
+            Some(max) if width > max || height > max => {
+                Err(OptionsError::SizeTooLarge { width, height, max })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Invalid [`PixelsOptions`] values.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum OptionsError {
+    /// The buffer width or height is zero.
+    ZeroSize { width: u32, height: u32 },
+    /// The scale factor is zero, negative, infinite or NaN.
+    InvalidScaleFactor(f32),
+    /// The buffer is larger than the GPU's maximum texture dimension.
+    SizeTooLarge { width: u32, height: u32, max: u32 },
+    /// The minimum or maximum buffer size is empty, or the minimum exceeds the maximum.
+    InvalidSizeLimits {
+        min: Option<(u32, u32)>,
+        max: Option<(u32, u32)>,
+    },
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionsError::ZeroSize { width, height } => write!(
+                f,
+                "buffer size {width}x{height} is empty, set `width` and `height` to at least 1"
+            ),
+            OptionsError::InvalidScaleFactor(scale_factor) => write!(
+                f,
+                "scale factor {scale_factor} is invalid, set `scale_factor` to a finite number \
+                 greater than 0"
+            ),
+            OptionsError::SizeTooLarge { width, height, max } => write!(
+                f,
+                "buffer size {width}x{height} exceeds the maximum texture dimension {max} of the \
+                 GPU, reduce `width` and `height` or increase `scale_factor`"
+            ),
+            OptionsError::InvalidSizeLimits { min, max } => write!(
+                f,
+                "buffer size limits min {min:?} and max {max:?} are invalid, use non-zero sizes \
+                 with `min_size` no larger than `max_size`"
+            ),
         }
     }
 }
 
+impl Error for OptionsError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -41,5 +237,147 @@ mod tests {
         assert_eq!(options.scale_factor, 1.0);
         assert!(options.auto_resize_buffer);
         assert!(options.auto_resize_surface);
+        assert_eq!(options.scaling_mode, ScalingMode::PixelPerfect);
+        assert_eq!(options.pixel_format, FrameFormat::Rgba8);
+        assert_eq!(options.texture_color_space, None);
+        assert_eq!(options.surface_color_space, None);
+    }
+
+    #[test]
+    fn scaling_mode_mapping_matches_pixels() {
+        assert!(matches!(
+            pixels::ScalingMode::from(ScalingMode::PixelPerfect),
+            pixels::ScalingMode::PixelPerfect
+        ));
+        assert!(matches!(
+            pixels::ScalingMode::from(ScalingMode::Fill),
+            pixels::ScalingMode::Fill
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn options_load_from_partial_ron() {
+        let options: PixelsOptions =
+            ron::from_str("(width: 320, height: 180, scaling_mode: Fill)").unwrap();
+
+        assert_eq!(
+            options,
+            PixelsOptions {
+                width: 320,
+                height: 180,
+                scaling_mode: ScalingMode::Fill,
+                ..default()
+            }
+        );
+        let text = ron::to_string(&options).unwrap();
+        assert_eq!(ron::from_str::<PixelsOptions>(&text).unwrap(), options);
+    }
+
+    #[test]
+    fn constructor_rejects_empty_buffers() {
+        assert_eq!(
+            PixelsOptions::new(0, 180),
+            Err(OptionsError::ZeroSize {
+                width: 0,
+                height: 180
+            })
+        );
+        assert_eq!(
+            PixelsOptions::new(320, 0),
+            Err(OptionsError::ZeroSize {
+                width: 320,
+                height: 0
+            })
+        );
+        assert_eq!(
+            PixelsOptions::new(320, 180).map(|options| (options.width, options.height)),
+            Ok((320, 180))
+        );
+    }
+
+    #[test]
+    fn validation_rejects_invalid_scale_factors() {
+        for scale_factor in [0.0, -2.0, f32::INFINITY] {
+            let options = PixelsOptions {
+                scale_factor,
+                ..default()
+            };
+            assert_eq!(
+                options.validated(),
+                Err(OptionsError::InvalidScaleFactor(scale_factor))
+            );
+        }
+
+        let options = PixelsOptions {
+            scale_factor: f32::NAN,
+            ..default()
+        };
+        assert!(matches!(
+            options.validate(),
+            Err(OptionsError::InvalidScaleFactor(scale_factor)) if scale_factor.is_nan()
+        ));
+    }
+
+    #[test]
+    fn validation_rejects_sizes_beyond_gpu_limits() {
+        let limits = wgpu::Limits::downlevel_webgl2_defaults();
+        let options = PixelsOptions {
+            width: limits.max_texture_dimension_2d + 1,
+            height: 16,
+            ..default()
+        };
+
+        assert_eq!(options.validate(), Ok(()));
+        assert_eq!(
+            options.validate_for_limits(&limits),
+            Err(OptionsError::SizeTooLarge {
+                width: limits.max_texture_dimension_2d + 1,
+                height: 16,
+                max: limits.max_texture_dimension_2d,
+            })
+        );
+    }
+
+    #[test]
+    fn validation_rejects_invalid_size_limits() {
+        for (min_size, max_size) in [
+            (Some((0, 10)), None),
+            (None, Some((10, 0))),
+            (Some((200, 100)), Some((100, 100))),
+        ] {
+            let options = PixelsOptions {
+                min_size,
+                max_size,
+                ..default()
+            };
+            assert_eq!(
+                options.validate(),
+                Err(OptionsError::InvalidSizeLimits {
+                    min: min_size,
+                    max: max_size
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn effective_scale_factor_respects_size_limits() {
+        let options = PixelsOptions {
+            scale_factor: 2.0,
+            min_size: Some((320, 180)),
+            max_size: Some((640, 360)),
+            ..default()
+        };
+
+        assert_eq!(
+            options.effective_scale_factor(Vec2::new(1280.0, 720.0)),
+            2.0
+        );
+        assert_eq!(
+            options.effective_scale_factor(Vec2::new(3840.0, 2160.0)),
+            6.0
+        );
+        assert_eq!(options.effective_scale_factor(Vec2::new(480.0, 270.0)), 1.5);
     }
 }
@@ -0,0 +1,402 @@
+use crate::prelude::*;
+
+use bevy::{ecs::entity::EntityHashMap, prelude::*};
+
+/// Number of colors in a [`Palette`].
+pub const PALETTE_SIZE: usize = 256;
+
+/// 256-entry RGBA palette used to resolve an [`IndexedFrame`]. Adding this component to an entity
+/// with [`PixelsOptions`] enables indexed color mode for its pixel buffer.
+///
+/// Changing the palette recolors the buffer on the next frame without redrawing the indices.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct Palette(pub [[u8; 4]; PALETTE_SIZE]);
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::grayscale()
+    }
+}
+
+impl Palette {
+    /// Palette where index `i` is the opaque gray `i`.
+    pub fn grayscale() -> Self {
+        Palette(std::array::from_fn(|index| {
+            let value = index as u8;
+            [value, value, value, 0xff]
+        }))
+    }
+
+    /// Palette starting with `colors`. Remaining entries are opaque black and extra colors are
+    /// ignored.
+    pub fn from_colors(colors: &[[u8; 4]]) -> Self {
+        let mut palette = Palette([[0x00, 0x00, 0x00, 0xff]; PALETTE_SIZE]);
+        for (entry, color) in palette.0.iter_mut().zip(colors) {
+            *entry = *color;
+        }
+        palette
+    }
+
+    /// Color of `index`.
+    pub fn color(&self, index: u8) -> [u8; 4] {
+        self.0[index as usize]
+    }
+
+    /// Set the color of `index`.
+    pub fn set_color(&mut self, index: u8, color: [u8; 4]) {
+        self.0[index as usize] = color;
+    }
+}
+
+/// Range of palette entries rotated by a [`PaletteCycle`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CycleRange {
+    /// First palette index of the range.
+    pub start: u8,
+    /// Last palette index of the range, inclusive.
+    pub end: u8,
+    /// Rotation speed in entries per second. Negative rates rotate in reverse.
+    pub rate: f32,
+    progress: f32,
+}
+
+impl CycleRange {
+    /// Rotate the entries `start..=end` at `rate` entries per second.
+    pub fn new(start: u8, end: u8, rate: f32) -> Self {
+        CycleRange {
+            start,
+            end,
+            rate,
+            progress: 0.0,
+        }
+    }
+}
+
+/// Classic color cycling: rotates ranges of the entity's [`Palette`] over time. Only the palette
+/// changes, so the buffer is recolored without redrawing.
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct PaletteCycle {
+    /// Ranges to rotate, each at its own rate.
+    pub ranges: Vec<CycleRange>,
+}
+
+impl PaletteCycle {
+    /// Create a cycle rotating the given ranges.
+    pub fn new(ranges: impl IntoIterator<Item = CycleRange>) -> Self {
+        PaletteCycle {
+            ranges: ranges.into_iter().collect(),
+        }
+    }
+
+    /// Advance every range by `seconds`, rotating `palette` by each whole step reached. Returns
+    /// `true` if the palette changed.
+    pub fn advance(&mut self, seconds: f32, palette: &mut Palette) -> bool {
+        let mut changed = false;
+        for range in &mut self.ranges {
+            range.progress += seconds * range.rate;
+            let steps = range.progress.trunc();
+            range.progress -= steps;
+
+            let (start, end) = (range.start as usize, range.end as usize);
+            if steps == 0.0 || start >= end {
+                continue;
+            }
+
+            let length = end - start + 1;
+            let steps = (steps as i64).rem_euclid(length as i64) as usize;
+            palette.0[start..=end].rotate_right(steps);
+            changed |= steps != 0;
+        }
+        changed
+    }
+}
+
+/// Buffer of 8-bit palette indices, created for entities with a [`Palette`] and resized with their
+/// [`PixelsOptions`]. Draw indices here instead of to the RGBA frame; they are resolved through
+/// the palette into the pixel buffer at the start of the [`Render`] schedule, only when the
+/// indices or the palette changed.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct IndexedFrame {
+    width: u32,
+    height: u32,
+    indices: Vec<u8>,
+}
+
+impl IndexedFrame {
+    /// Create a frame of the given size, filled with index zero.
+    pub fn new(width: u32, height: u32) -> Self {
+        IndexedFrame {
+            width,
+            height,
+            indices: vec![0; width as usize * height as usize],
+        }
+    }
+
+    /// Width of the frame in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the frame in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get a mutable slice of the palette indices, one byte per pixel.
+    pub fn indices_mut(&mut self) -> &mut [u8] {
+        &mut self.indices
+    }
+
+    /// Get an immutable slice of the palette indices, one byte per pixel.
+    pub fn indices(&self) -> &[u8] {
+        &self.indices
+    }
+
+    /// Set the index of the pixel at `(x, y)`. Positions outside the frame are ignored.
+    pub fn set(&mut self, x: u32, y: u32, index: u8) {
+        if x < self.width && y < self.height {
+            self.indices[(y * self.width + x) as usize] = index;
+        }
+    }
+
+    /// Resize the frame. Existing contents are discarded and the frame is filled with index zero.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        *self = IndexedFrame::new(width, height);
+    }
+
+    /// Write the RGBA color of every index into `target`. Does nothing if `target` is not an RGBA8
+    /// frame of the same size.
+    pub fn resolve(&self, palette: &Palette, target: &mut [u8]) {
+        self.resolve_with_format(palette, target, PixelFormat::Rgba8);
+    }
+
+    /// Write the color of every index into `target`, a frame of `format` pixels. Does nothing if
+    /// `target` is not a frame of the same size.
+    pub fn resolve_with_format(&self, palette: &Palette, target: &mut [u8], format: PixelFormat) {
+        let bytes_per_pixel = format.bytes_per_pixel();
+        if target.len() != self.indices.len() * bytes_per_pixel {
+            return;
+        }
+
+        let mut colors = vec![0; PALETTE_SIZE * bytes_per_pixel];
+        for (color, &rgba) in colors.chunks_exact_mut(bytes_per_pixel).zip(&palette.0) {
+            format.write(rgba, color);
+        }
+        for (pixel, &index) in target.chunks_exact_mut(bytes_per_pixel).zip(&self.indices) {
+            let offset = index as usize * bytes_per_pixel;
+            pixel.copy_from_slice(&colors[offset..offset + bytes_per_pixel]);
+        }
+    }
+}
+
+/// Advance every [`PaletteCycle`], rotating its [`Palette`]. Skipped in apps without [`Time`].
+pub fn cycle_palettes(time: If<Res<Time>>, mut query: Query<(&mut PaletteCycle, Mut<Palette>)>) {
+    for (mut cycle, mut palette) in &mut query {
+        // Only mark the palette changed when entries actually rotated.
+        if cycle.advance(time.delta_secs(), palette.bypass_change_detection()) {
+            palette.set_changed();
+        }
+    }
+}
+
+/// Create an [`IndexedFrame`] for entities with a [`Palette`].
+#[allow(clippy::type_complexity)]
+pub fn create_indexed_frames(
+    mut commands: Commands,
+    query: Query<(Entity, &PixelsOptions), (With<Palette>, Without<IndexedFrame>)>,
+) {
+    for (entity, options) in &query {
+        commands
+            .entity(entity)
+            .insert(IndexedFrame::new(options.width, options.height));
+    }
+}
+
+/// Resize [`IndexedFrame`]s whose [`PixelsOptions`] dimensions changed.
+pub fn synchronize_indexed_frames(
+    mut frames: Query<(&PixelsOptions, &mut IndexedFrame), Changed<PixelsOptions>>,
+) {
+    for (options, mut frame) in &mut frames {
+        if (frame.width(), frame.height()) != (options.width, options.height) {
+            frame.resize(options.width, options.height);
+        }
+    }
+}
+
+/// Resolve changed [`IndexedFrame`]s and [`Palette`]s into their RGBA pixel buffers. Frames are
+/// also resolved into new pixel buffers and after a buffer is resized, which clears it.
+#[allow(clippy::type_complexity)]
+pub fn resolve_palettes(
+    mut query: Query<(
+        Entity,
+        Ref<IndexedFrame>,
+        Ref<Palette>,
+        Option<Mut<PixelsWrapper>>,
+        Option<Mut<PixelsBuffer>>,
+    )>,
+    mut resolved_sizes: Local<EntityHashMap<(Option<UVec2>, Option<UVec2>)>>,
+) {
+    resolved_sizes.retain(|entity, _| query.contains(*entity));
+
+    for (entity, frame, palette, wrapper, buffer) in &mut query {
+        let sizes = (
+            wrapper.as_ref().map(|wrapper| {
+                let extent = wrapper.pixels.context().texture_extent;
+                UVec2::new(extent.width, extent.height)
+            }),
+            buffer
+                .as_ref()
+                .map(|buffer| UVec2::new(buffer.width(), buffer.height())),
+        );
+        let resized = resolved_sizes.insert(entity, sizes) != Some(sizes);
+        if !resized && !frame.is_changed() && !palette.is_changed() {
+            continue;
+        }
+
+        if let Some(mut wrapper) = wrapper
+            && let Some(format) = wrapper.pixel_format()
+        {
+            frame.resolve_with_format(&palette, wrapper.pixels.frame_mut(), format);
+        }
+        if let Some(mut buffer) = buffer {
+            frame.resolve(&palette, buffer.frame_mut());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grayscale_palette_maps_index_to_gray() {
+        let palette = Palette::default();
+
+        assert_eq!(palette.color(0), [0, 0, 0, 0xff]);
+        assert_eq!(palette.color(128), [128, 128, 128, 0xff]);
+        assert_eq!(palette.color(255), [255, 255, 255, 0xff]);
+    }
+
+    #[test]
+    fn from_colors_fills_remaining_entries_with_black() {
+        let palette = Palette::from_colors(&[[1, 2, 3, 4], [5, 6, 7, 8]]);
+
+        assert_eq!(palette.color(0), [1, 2, 3, 4]);
+        assert_eq!(palette.color(1), [5, 6, 7, 8]);
+        assert_eq!(palette.color(2), [0, 0, 0, 0xff]);
+    }
+
+    #[test]
+    fn cycle_rotates_range_by_whole_steps() {
+        let mut palette = Palette::default();
+        let mut cycle = PaletteCycle::new([CycleRange::new(1, 3, 2.0)]);
+
+        assert!(!cycle.advance(0.25, &mut palette));
+        assert_eq!(palette, Palette::default());
+
+        assert!(cycle.advance(0.25, &mut palette));
+        assert_eq!(
+            palette.0[..5]
+                .iter()
+                .map(|color| color[0])
+                .collect::<Vec<_>>(),
+            vec![0, 3, 1, 2, 4]
+        );
+    }
+
+    #[test]
+    fn cycle_with_negative_rate_rotates_in_reverse() {
+        let mut palette = Palette::default();
+        let mut cycle = PaletteCycle::new([CycleRange::new(1, 3, -1.0)]);
+
+        cycle.advance(1.0, &mut palette);
+
+        assert_eq!(
+            palette.0[..5]
+                .iter()
+                .map(|color| color[0])
+                .collect::<Vec<_>>(),
+            vec![0, 2, 3, 1, 4]
+        );
+    }
+
+    #[test]
+    fn full_rotation_leaves_palette_unchanged() {
+        let mut palette = Palette::default();
+        let mut cycle = PaletteCycle::new([CycleRange::new(0, 3, 4.0)]);
+
+        assert!(!cycle.advance(1.0, &mut palette));
+        assert_eq!(palette, Palette::default());
+    }
+
+    #[test]
+    fn set_ignores_positions_outside_frame() {
+        let mut frame = IndexedFrame::new(2, 2);
+
+        frame.set(1, 1, 7);
+        frame.set(2, 0, 9);
+
+        assert_eq!(frame.indices(), &[0, 0, 0, 7]);
+    }
+
+    #[test]
+    fn resolve_writes_palette_colors() {
+        let mut frame = IndexedFrame::new(2, 1);
+        frame.indices_mut().copy_from_slice(&[1, 0]);
+        let palette = Palette::from_colors(&[[9, 9, 9, 9], [1, 2, 3, 4]]);
+        let mut target = vec![0; 8];
+
+        frame.resolve(&palette, &mut target);
+
+        assert_eq!(target, vec![1, 2, 3, 4, 9, 9, 9, 9]);
+    }
+
+    #[test]
+    fn resolve_converts_colors_to_frame_format() {
+        let mut frame = IndexedFrame::new(2, 1);
+        frame.indices_mut().copy_from_slice(&[1, 0]);
+        let palette = Palette::from_colors(&[[9, 9, 9, 9], [1, 2, 3, 4]]);
+        let mut target = vec![0; 8];
+
+        frame.resolve_with_format(&palette, &mut target, PixelFormat::Bgra8);
+
+        assert_eq!(target, vec![3, 2, 1, 4, 9, 9, 9, 9]);
+    }
+
+    #[test]
+    fn resolve_skips_mismatched_targets() {
+        let frame = IndexedFrame::new(2, 1);
+        let mut target = vec![0xaa; 4];
+
+        frame.resolve(&Palette::default(), &mut target);
+
+        assert_eq!(target, vec![0xaa; 4]);
+    }
+
+    #[test]
+    fn resolve_palettes_reacts_to_new_and_resized_buffers() {
+        let mut world = World::new();
+        let mut frame = IndexedFrame::new(1, 1);
+        frame.indices_mut()[0] = 1;
+        let palette = Palette::from_colors(&[[0, 0, 0, 0], [1, 2, 3, 4]]);
+        let entity = world.spawn((frame, palette)).id();
+        world.run_system_cached(resolve_palettes).unwrap();
+
+        world.entity_mut(entity).insert(PixelsBuffer::new(1, 1));
+        world.run_system_cached(resolve_palettes).unwrap();
+        assert_eq!(
+            world.get::<PixelsBuffer>(entity).unwrap().frame(),
+            [1, 2, 3, 4]
+        );
+
+        // The buffer and frame sizes briefly disagree, as when a window is resized.
+        world.get_mut::<PixelsBuffer>(entity).unwrap().resize(2, 1);
+        world.run_system_cached(resolve_palettes).unwrap();
+        world.get_mut::<PixelsBuffer>(entity).unwrap().resize(1, 1);
+        world.run_system_cached(resolve_palettes).unwrap();
+        assert_eq!(
+            world.get::<PixelsBuffer>(entity).unwrap().frame(),
+            [1, 2, 3, 4]
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use crate::{PALETTE_SIZE, Palette};
+
+use std::{error::Error, fmt};
+
+/// Palette file formats supported by [`Palette::load`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PaletteFormat {
+    /// JASC-PAL text palette (`.pal`), as written by Paint Shop Pro and Lospec.
+    Jasc,
+    /// GIMP palette (`.gpl`).
+    Gpl,
+    /// Adobe Color Table (`.act`), 256 RGB triplets with an optional color count and transparent
+    /// index.
+    Act,
+    /// One `RRGGBB` or `RRGGBBAA` hex color per line (`.hex`), optionally prefixed with `#`.
+    Hex,
+    /// Paint.NET palette (`.txt`), one `AARRGGBB` hex color per line with `;` comments.
+    PaintNet,
+    /// PNG image whose pixels, in row-major order, are the palette colors. Requires the `png`
+    /// feature.
+    #[cfg(feature = "png")]
+    Png,
+}
+
+impl PaletteFormat {
+    /// Guess the format from a file extension, ignoring case.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "pal" => Some(PaletteFormat::Jasc),
+            "gpl" => Some(PaletteFormat::Gpl),
+            "act" => Some(PaletteFormat::Act),
+            "hex" => Some(PaletteFormat::Hex),
+            "txt" => Some(PaletteFormat::PaintNet),
+            #[cfg(feature = "png")]
+            "png" => Some(PaletteFormat::Png),
+            _ => None,
+        }
+    }
+}
+
+/// Reasons a palette file can fail to load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PaletteError {
+    /// The file is not valid UTF-8 text.
+    InvalidText,
+    /// The file does not start with the header expected by its format.
+    InvalidHeader,
+    /// The color on the given (one-based) line could not be parsed.
+    InvalidColor { line: usize },
+    /// The file contains more colors than fit in a [`Palette`].
+    TooManyColors(usize),
+    /// The file length does not match its format.
+    InvalidLength(usize),
+    /// The PNG image could not be decoded.
+    #[cfg(feature = "png")]
+    Png(String),
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::InvalidText => write!(f, "palette file is not valid UTF-8 text"),
+            PaletteError::InvalidHeader => write!(f, "palette file has an invalid header"),
+            PaletteError::InvalidColor { line } => write!(f, "invalid color on line {line}"),
+            PaletteError::TooManyColors(count) => write!(
+                f,
+                "palette has {count} colors but at most {PALETTE_SIZE} are supported"
+            ),
+            PaletteError::InvalidLength(length) => {
+                write!(f, "palette file has an invalid length of {length} bytes")
+            }
+            #[cfg(feature = "png")]
+            PaletteError::Png(error) => write!(f, "failed to decode palette image: {error}"),
+        }
+    }
+}
+
+impl Error for PaletteError {}
+
+impl Palette {
+    /// Load a palette from the contents of a file in the given format.
+    pub fn load(bytes: &[u8], format: PaletteFormat) -> Result<Palette, PaletteError> {
+        match format {
+            PaletteFormat::Jasc => parse_jasc(text(bytes)?),
+            PaletteFormat::Gpl => parse_gpl(text(bytes)?),
+            PaletteFormat::Act => parse_act(bytes),
+            PaletteFormat::Hex => parse_hex(text(bytes)?, false),
+            PaletteFormat::PaintNet => parse_hex(text(bytes)?, true),
+            #[cfg(feature = "png")]
+            PaletteFormat::Png => parse_png(bytes),
+        }
+    }
+}
+
+fn text(bytes: &[u8]) -> Result<&str, PaletteError> {
+    std::str::from_utf8(bytes).map_err(|_| PaletteError::InvalidText)
+}
+
+fn palette(colors: Vec<[u8; 4]>) -> Result<Palette, PaletteError> {
+    if colors.len() > PALETTE_SIZE {
+        return Err(PaletteError::TooManyColors(colors.len()));
+    }
+    Ok(Palette::from_colors(&colors))
+}
+
+/// Parse the first three whitespace separated decimal channels of a line.
+fn rgb(line: &str, number: usize) -> Result<[u8; 4], PaletteError> {
+    let mut channels = line.split_whitespace().map(str::parse::<u8>);
+    match (channels.next(), channels.next(), channels.next()) {
+        (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Ok([r, g, b, 0xff]),
+        _ => Err(PaletteError::InvalidColor { line: number }),
+    }
+}
+
+fn parse_jasc(text: &str) -> Result<Palette, PaletteError> {
+    let mut lines = text.lines().map(str::trim).enumerate();
+    if !matches!(lines.next(), Some((_, "JASC-PAL"))) || !matches!(lines.next(), Some((_, "0100")))
+    {
+        return Err(PaletteError::InvalidHeader);
+    }
+    let count: usize = lines
+        .next()
+        .and_then(|(_, count)| count.parse().ok())
+        .ok_or(PaletteError::InvalidHeader)?;
+
+    let colors = lines
+        .filter(|(_, line)| !line.is_empty())
+        .take(count)
+        .map(|(index, line)| rgb(line, index + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+    palette(colors)
+}
+
+fn parse_gpl(text: &str) -> Result<Palette, PaletteError> {
+    let mut lines = text.lines().map(str::trim).enumerate();
+    if !matches!(lines.next(), Some((_, "GIMP Palette"))) {
+        return Err(PaletteError::InvalidHeader);
+    }
+
+    let colors = lines
+        .filter(|(_, line)| {
+            !line.is_empty()
+                && !line.starts_with('#')
+                && !line.starts_with("Name:")
+                && !line.starts_with("Columns:")
+        })
+        .map(|(index, line)| rgb(line, index + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+    palette(colors)
+}
+
+fn parse_act(bytes: &[u8]) -> Result<Palette, PaletteError> {
+    let (count, transparent) = match bytes.len() {
+        768 => (PALETTE_SIZE, None),
+        772 => {
+            let count = u16::from_be_bytes([bytes[768], bytes[769]]) as usize;
+            let transparent = u16::from_be_bytes([bytes[770], bytes[771]]);
+            (
+                count.min(PALETTE_SIZE),
+                (transparent != 0xffff).then_some(transparent as usize),
+            )
+        }
+        length => return Err(PaletteError::InvalidLength(length)),
+    };
+
+    let mut colors: Vec<[u8; 4]> = bytes[..count * 3]
+        .chunks_exact(3)
+        .map(|rgb| [rgb[0], rgb[1], rgb[2], 0xff])
+        .collect();
+    if let Some(color) = transparent.and_then(|index| colors.get_mut(index)) {
+        color[3] = 0x00;
+    }
+    palette(colors)
+}
+
+/// Parse hex colors, with the alpha channel of eight digit colors first when `alpha_first`.
+fn parse_hex(text: &str, alpha_first: bool) -> Result<Palette, PaletteError> {
+    let colors = text
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with(';'))
+        .map(|(index, line)| {
+            let digits = line.trim_start_matches('#');
+            let invalid = PaletteError::InvalidColor { line: index + 1 };
+            if !matches!(digits.len(), 6 | 8) || !digits.is_ascii() {
+                return Err(invalid);
+            }
+            let channel = |start: usize| {
+                u8::from_str_radix(&digits[start..start + 2], 16).map_err(|_| invalid.clone())
+            };
+            match (digits.len(), alpha_first) {
+                (8, true) => Ok([channel(2)?, channel(4)?, channel(6)?, channel(0)?]),
+                (8, false) => Ok([channel(0)?, channel(2)?, channel(4)?, channel(6)?]),
+                _ => Ok([channel(0)?, channel(2)?, channel(4)?, 0xff]),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    palette(colors)
+}
+
+#[cfg(feature = "png")]
+fn parse_png(bytes: &[u8]) -> Result<Palette, PaletteError> {
+    use png::{ColorType, Decoder, Transformations};
+
+    let png_error = |error: png::DecodingError| PaletteError::Png(error.to_string());
+    let mut decoder = Decoder::new(std::io::Cursor::new(bytes));
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(png_error)?;
+    let mut buffer = vec![0; reader.output_buffer_size().unwrap_or_default()];
+    let info = reader.next_frame(&mut buffer).map_err(png_error)?;
+    let pixels = &buffer[..info.buffer_size()];
+
+    let colors = match info.color_type {
+        ColorType::Rgba => pixels
+            .chunks_exact(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+            .collect(),
+        ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], 0xff])
+            .collect(),
+        ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+            .collect(),
+        ColorType::Grayscale => pixels
+            .iter()
+            .map(|&value| [value, value, value, 0xff])
+            .collect(),
+        ColorType::Indexed => unreachable!("indexed images are expanded by the decoder"),
+    };
+    palette(colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [0xff, 0x00, 0x00, 0xff];
+    const GREEN: [u8; 4] = [0x00, 0x80, 0x00, 0xff];
+
+    #[test]
+    fn extensions_map_to_formats() {
+        assert_eq!(
+            PaletteFormat::from_extension("PAL"),
+            Some(PaletteFormat::Jasc)
+        );
+        assert_eq!(
+            PaletteFormat::from_extension("gpl"),
+            Some(PaletteFormat::Gpl)
+        );
+        assert_eq!(
+            PaletteFormat::from_extension("act"),
+            Some(PaletteFormat::Act)
+        );
+        assert_eq!(
+            PaletteFormat::from_extension("hex"),
+            Some(PaletteFormat::Hex)
+        );
+        assert_eq!(
+            PaletteFormat::from_extension("txt"),
+            Some(PaletteFormat::PaintNet)
+        );
+        assert_eq!(PaletteFormat::from_extension("bmp"), None);
+    }
+
+    #[test]
+    fn loads_jasc() {
+        let file = "JASC-PAL\r\n0100\r\n2\r\n255 0 0\r\n0 128 0\r\n";
+
+        let palette = Palette::load(file.as_bytes(), PaletteFormat::Jasc).unwrap();
+
+        assert_eq!(palette, Palette::from_colors(&[RED, GREEN]));
+    }
+
+    #[test]
+    fn rejects_jasc_without_header() {
+        assert_eq!(
+            Palette::load(b"2\n255 0 0\n", PaletteFormat::Jasc),
+            Err(PaletteError::InvalidHeader)
+        );
+    }
+
+    #[test]
+    fn loads_gpl_skipping_metadata_and_names() {
+        let file =
+            "GIMP Palette\nName: Test\nColumns: 2\n#\n255   0   0\tRed\n  0 128   0\tGreen\n";
+
+        let palette = Palette::load(file.as_bytes(), PaletteFormat::Gpl).unwrap();
+
+        assert_eq!(palette, Palette::from_colors(&[RED, GREEN]));
+    }
+
+    #[test]
+    fn reports_line_of_invalid_gpl_color() {
+        let file = "GIMP Palette\n255 0 0\n0 300 0\n";
+
+        assert_eq!(
+            Palette::load(file.as_bytes(), PaletteFormat::Gpl),
+            Err(PaletteError::InvalidColor { line: 3 })
+        );
+    }
+
+    #[test]
+    fn loads_act_with_count_and_transparent_index() {
+        let mut file = vec![0; 772];
+        file[..6].copy_from_slice(&[0xff, 0x00, 0x00, 0x00, 0x80, 0x00]);
+        file[768..].copy_from_slice(&[0x00, 0x02, 0x00, 0x01]);
+
+        let palette = Palette::load(&file, PaletteFormat::Act).unwrap();
+
+        assert_eq!(
+            palette,
+            Palette::from_colors(&[RED, [0x00, 0x80, 0x00, 0x00]])
+        );
+    }
+
+    #[test]
+    fn loads_full_act() {
+        let file: Vec<u8> = (0..=255).flat_map(|value| [value, value, value]).collect();
+
+        assert_eq!(
+            Palette::load(&file, PaletteFormat::Act),
+            Ok(Palette::grayscale())
+        );
+    }
+
+    #[test]
+    fn rejects_act_with_invalid_length() {
+        assert_eq!(
+            Palette::load(&[0; 10], PaletteFormat::Act),
+            Err(PaletteError::InvalidLength(10))
+        );
+    }
+
+    #[test]
+    fn loads_hex_with_optional_prefix_and_alpha() {
+        let file = "ff0000\n\n#00800080\n";
+
+        let palette = Palette::load(file.as_bytes(), PaletteFormat::Hex).unwrap();
+
+        assert_eq!(
+            palette,
+            Palette::from_colors(&[RED, [0x00, 0x80, 0x00, 0x80]])
+        );
+    }
+
+    #[test]
+    fn loads_paint_net_with_alpha_first() {
+        let file = "; paint.net Palette File\r\n; Colors: 2\r\nFFFF0000\r\n80008000\r\n";
+
+        let palette = Palette::load(file.as_bytes(), PaletteFormat::PaintNet).unwrap();
+
+        assert_eq!(
+            palette,
+            Palette::from_colors(&[RED, [0x00, 0x80, 0x00, 0x80]])
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_hex() {
+        assert_eq!(
+            Palette::load(b"ff0000\nxyz123\n", PaletteFormat::Hex),
+            Err(PaletteError::InvalidColor { line: 2 })
+        );
+    }
+
+    #[test]
+    fn rejects_palettes_with_too_many_colors() {
+        let file = "000000\n".repeat(257);
+
+        assert_eq!(
+            Palette::load(file.as_bytes(), PaletteFormat::Hex),
+            Err(PaletteError::TooManyColors(257))
+        );
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn loads_png_strip() {
+        let mut file = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut file, 2, 1);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer
+                .write_image_data(&[0xff, 0x00, 0x00, 0x00, 0x80, 0x00])
+                .unwrap();
+        }
+
+        let palette = Palette::load(&file, PaletteFormat::Png).unwrap();
+
+        assert_eq!(palette, Palette::from_colors(&[RED, GREEN]));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{DrawTarget, FormatError, PixelFormat, PixelsBuffer, PixelsWrapper};
+
+use bevy::{
+    prelude::*,
+    tasks::{ComputeTaskPool, ParallelSliceMut, TaskPool},
+};
+
+/// Number of row bands per compute thread, so uneven rows still balance across threads.
+const BANDS_PER_THREAD: usize = 4;
+
+/// Run `f` for every row of a `frame` of `format` pixels that is `width` pixels wide, splitting
+/// the rows into bands processed in parallel on the [`ComputeTaskPool`]. Returns once every row is
+/// done.
+///
+/// `f` receives the row index and the row's bytes.
+pub fn par_rows_mut(
+    mut frame: &mut [u8],
+    width: u32,
+    format: PixelFormat,
+    f: impl Fn(u32, &mut [u8]) + Send + Sync,
+) {
+    let row_len = width as usize * format.bytes_per_pixel();
+    if row_len == 0 || frame.is_empty() {
+        return;
+    }
+
+    let pool = ComputeTaskPool::get_or_init(TaskPool::default);
+    let rows = frame.len() / row_len;
+    let bands = (pool.thread_num() * BANDS_PER_THREAD).max(1);
+    let band_rows = rows.div_ceil(bands).max(1);
+
+    frame.par_chunk_map_mut(pool, band_rows * row_len, |band, chunk| {
+        for (offset, row) in chunk.chunks_exact_mut(row_len).enumerate() {
+            f((band * band_rows + offset) as u32, row);
+        }
+    });
+}
+
+/// Run `f` for every pixel of an RGBA8 `frame` that is `width` pixels wide, in parallel row bands
+/// as with [`par_rows_mut`].
+///
+/// `f` receives the pixel position and its RGBA bytes.
+pub fn par_pixels_mut(frame: &mut [u8], width: u32, f: impl Fn(UVec2, &mut [u8; 4]) + Send + Sync) {
+    par_rows_mut(frame, width, PixelFormat::Rgba8, |y, row| {
+        for (x, pixel) in row.as_chunks_mut::<4>().0.iter_mut().enumerate() {
+            f(UVec2::new(x as u32, y), pixel);
+        }
+    });
+}
+
+impl PixelsWrapper {
+    /// Update the frame row by row in parallel, see [`par_rows_mut`]. Rows hold pixels in the
+    /// frame's [`PixelFormat`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError::UnsupportedTextureFormat`] when the frame has no [`PixelFormat`].
+    pub fn par_rows_mut(
+        &mut self,
+        f: impl Fn(u32, &mut [u8]) + Send + Sync,
+    ) -> Result<(), FormatError> {
+        let context = self.pixels.context();
+        let format = self
+            .pixel_format()
+            .ok_or(FormatError::UnsupportedTextureFormat(
+                context.texture_format,
+            ))?;
+        let width = context.texture_extent.width;
+        par_rows_mut(self.pixels.frame_mut(), width, format, f);
+        Ok(())
+    }
+
+    /// Update an RGBA8 frame pixel by pixel in parallel, see [`par_pixels_mut`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError::UnsupportedPixelFormat`] when the frame is not
+    /// [`PixelFormat::Rgba8`]. Use [`par_rows_mut`](Self::par_rows_mut) for other formats.
+    pub fn par_pixels_mut(
+        &mut self,
+        f: impl Fn(UVec2, &mut [u8; 4]) + Send + Sync,
+    ) -> Result<(), FormatError> {
+        let context = self.pixels.context();
+        match self.pixel_format() {
+            Some(PixelFormat::Rgba8) => {}
+            Some(format) => return Err(FormatError::UnsupportedPixelFormat(format)),
+            None => {
+                return Err(FormatError::UnsupportedTextureFormat(
+                    context.texture_format,
+                ));
+            }
+        }
+        let width = context.texture_extent.width;
+        par_pixels_mut(self.pixels.frame_mut(), width, f);
+        Ok(())
+    }
+}
+
+impl PixelsBuffer {
+    /// Update the buffer row by row in parallel, see [`par_rows_mut`].
+    pub fn par_rows_mut(&mut self, f: impl Fn(u32, &mut [u8]) + Send + Sync) {
+        let width = self.width();
+        par_rows_mut(self.frame_mut(), width, PixelFormat::Rgba8, f);
+    }
+
+    /// Update the buffer pixel by pixel in parallel, see [`par_pixels_mut`].
+    pub fn par_pixels_mut(&mut self, f: impl Fn(UVec2, &mut [u8; 4]) + Send + Sync) {
+        let width = self.width();
+        par_pixels_mut(self.frame_mut(), width, f);
+    }
+}
+
+impl<M: Component> DrawTarget<'_, '_, M> {
+    /// Update the target frame row by row in parallel, see [`PixelsWrapper::par_rows_mut`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError::UnsupportedTextureFormat`] when the frame has no [`PixelFormat`].
+    pub fn par_rows_mut(
+        &mut self,
+        f: impl Fn(u32, &mut [u8]) + Send + Sync,
+    ) -> Result<(), FormatError> {
+        self.wrapper_mut().par_rows_mut(f)
+    }
+
+    /// Update an RGBA8 target frame pixel by pixel in parallel, see
+    /// [`PixelsWrapper::par_pixels_mut`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError::UnsupportedPixelFormat`] when the frame is not
+    /// [`PixelFormat::Rgba8`].
+    pub fn par_pixels_mut(
+        &mut self,
+        f: impl Fn(UVec2, &mut [u8; 4]) + Send + Sync,
+    ) -> Result<(), FormatError> {
+        self.wrapper_mut().par_pixels_mut(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_row_is_visited_once_with_its_index() {
+        let width = 3;
+        let height = 37;
+        let mut frame = vec![0; width * height * 4];
+
+        par_rows_mut(&mut frame, width as u32, PixelFormat::Rgba8, |y, row| {
+            assert_eq!(row.len(), width * 4);
+            for byte in row {
+                *byte += y as u8 + 1;
+            }
+        });
+
+        for (y, row) in frame.chunks_exact(width * 4).enumerate() {
+            assert!(row.iter().all(|byte| *byte == y as u8 + 1));
+        }
+    }
+
+    #[test]
+    fn rows_span_the_pixel_format_width() {
+        let mut frame = vec![0; 3 * 2 * 8];
+
+        par_rows_mut(&mut frame, 3, PixelFormat::Rgba16Float, |y, row| {
+            assert_eq!(row.len(), 3 * 8);
+            row.fill(y as u8 + 1);
+        });
+
+        assert!(frame[..24].iter().all(|byte| *byte == 1));
+        assert!(frame[24..].iter().all(|byte| *byte == 2));
+    }
+
+    #[test]
+    fn pixels_receive_their_position() {
+        let mut buffer = PixelsBuffer::new(5, 4);
+
+        buffer.par_pixels_mut(|position, pixel| {
+            *pixel = [position.x as u8, position.y as u8, 0, 255];
+        });
+
+        for (index, pixel) in buffer.frame().chunks_exact(4).enumerate() {
+            assert_eq!(pixel, [(index % 5) as u8, (index / 5) as u8, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn empty_frames_are_ignored() {
+        par_rows_mut(&mut [], 4, PixelFormat::Rgba8, |_, _| panic!("no rows"));
+        par_rows_mut(&mut [0; 8], 0, PixelFormat::Rgba8, |_, _| panic!("no rows"));
+    }
+}
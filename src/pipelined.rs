@@ -0,0 +1,284 @@
+//! Pipelined rendering: a window's frame is presented on the [`AsyncComputeTaskPool`] while the
+//! next frame is simulated.
+//!
+//! Ordering guarantees when [`PixelsPlugin::pipelined_rendering`] is enabled:
+//!
+//! - At the end of [`Render`] in frame N, every window whose frame changed lends its
+//!   [`PixelsWrapper`] (and [`PostProcessChain`]) to a render task. Windows with nothing to present
+//!   keep their wrapper.
+//! - From then until [`PrepareRedraw`] in frame N + 1, lent components are absent from the window
+//!   entity, so systems in `First` through [`Update`] run concurrently with the render and cannot
+//!   observe a wrapper being rendered.
+//! - [`PrepareRedraw`] runs every frame, before the schedule [`Draw`] runs after. It waits for the
+//!   render tasks and reinserts the components with their original change ticks, so lending is
+//!   invisible to `Added` and `Changed` filters. Buffer and surface sizes synchronized while the
+//!   wrapper was lent are applied at that point.
+//!
+//! The wrapper is lent rather than its frame copied: presenting needs the [`Pixels`] instance
+//! that owns the window surface, and pixels does not hand out its surface, so a render task
+//! cannot present a frame without it.
+//!
+//! [`Pixels`]: pixels::Pixels
+
+#[cfg(feature = "render")]
+use crate::system;
+use crate::{prelude::*, synchronization::DesiredSizes};
+
+use bevy::{
+    ecs::{change_detection::Tick, component::Mutable},
+    prelude::*,
+    tasks::{Task, block_on},
+};
+#[cfg(feature = "render")]
+use bevy::{
+    tasks::{AsyncComputeTaskPool, TaskPool},
+    window::WindowOccluded,
+};
+
+/// A component taken from an entity together with its change ticks.
+#[cfg_attr(not(feature = "render"), allow(dead_code))]
+pub(crate) struct Lent<T> {
+    value: T,
+    added: Tick,
+    changed: Tick,
+}
+
+impl<T: Component> Lent<T> {
+    /// Remove `T` from `entity`, remembering when it was added and last changed.
+    #[cfg_attr(not(feature = "render"), allow(dead_code))]
+    pub(crate) fn take(entity: &mut EntityWorldMut) -> Option<Self> {
+        let (added, changed) = entity
+            .get_ref::<T>()
+            .map(|value| (value.added(), value.last_changed()))?;
+        entity.take::<T>().map(|value| Lent {
+            value,
+            added,
+            changed,
+        })
+    }
+
+    /// Insert the component back into `entity` with its original change ticks.
+    #[cfg_attr(not(feature = "render"), allow(dead_code))]
+    pub(crate) fn restore(self, entity: &mut EntityWorldMut)
+    where
+        T: Component<Mutability = Mutable>,
+    {
+        entity.insert(self.value);
+        if let Some(mut value) = entity.get_mut::<T>() {
+            value.set_last_added(self.added);
+            value.set_last_changed(self.changed);
+        }
+    }
+}
+
+/// Returns lent components to their entity once a render task completes.
+type Restore = Box<dyn FnOnce(&mut EntityWorldMut) + Send>;
+
+/// A window frame being presented by a render task.
+#[derive(Component)]
+pub(crate) struct RenderInFlight {
+    task: Task<Restore>,
+    /// Sizes synchronized while the wrapper was lent, applied once it returns.
+    pub(crate) deferred: DesiredSizes,
+}
+
+impl RenderInFlight {
+    /// Run `render` on the [`AsyncComputeTaskPool`]. The returned closure is applied to the
+    /// entity by [`receive_frames`].
+    #[cfg(feature = "render")]
+    pub(crate) fn spawn(render: impl FnOnce() -> Restore + Send + 'static) -> Self {
+        let pool = AsyncComputeTaskPool::get_or_init(TaskPool::default);
+        RenderInFlight {
+            task: pool.spawn(async move { render() }),
+            deferred: DesiredSizes::default(),
+        }
+    }
+}
+
+/// Lend each changed window frame to a render task. Replaces the default render system in the
+/// [`Render`] schedule when rendering is pipelined.
+#[cfg(feature = "render")]
+#[allow(clippy::type_complexity)]
+pub fn submit_frames(
+    mut commands: Commands,
+    mut occluded: MessageReader<WindowOccluded>,
+    mut query: Query<(
+        Entity,
+        Ref<PixelsWrapper>,
+        Option<Ref<PostProcessChain>>,
+        Option<&mut DirtyRects>,
+    )>,
+) {
+    let revealed = system::revealed_windows(&mut occluded);
+
+    for (entity, wrapper, chain, dirty) in &mut query {
+        let changed = system::frame_changed(
+            wrapper.is_changed(),
+            dirty.as_deref(),
+            chain.as_ref().is_some_and(|chain| chain.is_changed()),
+        );
+        if !changed && !revealed.contains(&entity) {
+            continue;
+        }
+        if let Some(mut dirty) = dirty {
+            dirty.clear();
+        }
+
+        commands.entity(entity).queue(|mut entity: EntityWorldMut| {
+            let Some(wrapper) = Lent::<PixelsWrapper>::take(&mut entity) else {
+                return;
+            };
+            let mut chain = Lent::<PostProcessChain>::take(&mut entity);
+
+            entity.insert(RenderInFlight::spawn(move || {
+                system::render_frame(&wrapper.value, chain.as_mut().map(|chain| &mut chain.value));
+                Box::new(move |entity: &mut EntityWorldMut| {
+                    wrapper.restore(entity);
+                    if let Some(chain) = chain {
+                        chain.restore(entity);
+                    }
+                })
+            }));
+        });
+    }
+}
+
+/// Wait for the render tasks started in the previous frame and return the lent components.
+/// Runs in [`PrepareRedraw`], before any draw system.
+pub(crate) fn receive_frames(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut RenderInFlight)>,
+) {
+    for (entity, mut in_flight) in &mut query {
+        let restore = block_on(&mut in_flight.task);
+        let deferred = std::mem::take(&mut in_flight.deferred);
+
+        commands
+            .entity(entity)
+            .queue(move |mut entity: EntityWorldMut| {
+                entity.remove::<RenderInFlight>();
+                restore(&mut entity);
+
+                if deferred.is_empty() {
+                    return;
+                }
+                let Some(options) = entity.get::<PixelsOptions>().copied() else {
+                    return;
+                };
+                let id = entity.id();
+                if let Some(mut wrapper) = entity.get_mut::<PixelsWrapper>() {
+                    deferred.apply(id, &options, &mut wrapper);
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component)]
+    struct Lendable;
+
+    #[test]
+    fn lent_components_keep_their_change_ticks() {
+        let mut world = World::new();
+        let entity = world.spawn(Lendable).id();
+        world.increment_change_tick();
+        world
+            .entity_mut(entity)
+            .get_mut::<Lendable>()
+            .unwrap()
+            .set_changed();
+        let (added, changed) = {
+            let lendable = world.entity(entity).get_ref::<Lendable>().unwrap();
+            (lendable.added(), lendable.last_changed())
+        };
+        world.increment_change_tick();
+
+        let lent = Lent::<Lendable>::take(&mut world.entity_mut(entity)).unwrap();
+        assert!(!world.entity(entity).contains::<Lendable>());
+
+        world.increment_change_tick();
+        lent.restore(&mut world.entity_mut(entity));
+
+        let lendable = world.entity(entity).get_ref::<Lendable>().unwrap();
+        assert_eq!(lendable.added(), added);
+        assert_eq!(lendable.last_changed(), changed);
+    }
+
+    #[cfg(feature = "render")]
+    #[derive(Component)]
+    struct Borrower;
+
+    #[cfg(feature = "render")]
+    #[derive(Resource, Default)]
+    struct LendTrace {
+        present_in_update: Vec<bool>,
+        ticks_in_draw: Vec<Option<(Tick, Tick)>>,
+    }
+
+    #[cfg(feature = "render")]
+    fn lend(mut commands: Commands, query: Query<Entity, With<Lendable>>) {
+        for entity in &query {
+            commands.entity(entity).queue(|mut entity: EntityWorldMut| {
+                let Some(lent) = Lent::<Lendable>::take(&mut entity) else {
+                    return;
+                };
+                entity.insert(RenderInFlight::spawn(move || {
+                    Box::new(move |entity: &mut EntityWorldMut| lent.restore(entity))
+                }));
+            });
+        }
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn lent_components_are_absent_until_prepare_redraw() {
+        let mut app = App::new();
+        app.add_plugins(
+            PixelsPlugin::default()
+                .with_primary_window(None)
+                .with_pipelined_rendering(true),
+        )
+        .init_resource::<LendTrace>()
+        .add_systems(
+            Update,
+            |query: Query<Has<Lendable>, With<Borrower>>, mut trace: ResMut<LendTrace>| {
+                trace.present_in_update.extend(&query);
+            },
+        )
+        .add_systems(
+            Draw,
+            |query: Query<Option<Ref<Lendable>>, With<Borrower>>, mut trace: ResMut<LendTrace>| {
+                trace.ticks_in_draw.extend(
+                    query
+                        .iter()
+                        .map(|lendable| lendable.map(|l| (l.added(), l.last_changed()))),
+                );
+            },
+        )
+        .add_systems(Render, lend);
+        let entity = app.world_mut().spawn((Lendable, Borrower)).id();
+        let ticks = {
+            let lendable = app.world().entity(entity).get_ref::<Lendable>().unwrap();
+            Some((lendable.added(), lendable.last_changed()))
+        };
+
+        app.update();
+        app.update();
+        app.update();
+
+        let trace = app.world().resource::<LendTrace>();
+        assert_eq!(trace.present_in_update, vec![true, false, false]);
+        assert_eq!(trace.ticks_in_draw, vec![ticks; 3]);
+    }
+
+    #[test]
+    fn lending_a_missing_component_does_nothing() {
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+
+        assert!(Lent::<Lendable>::take(&mut world.entity_mut(entity)).is_none());
+    }
+}
@@ -1,19 +1,55 @@
-use crate::{diagnostic, prelude::*, synchronization, system};
+use crate::{
+    crt, diagnostic, dirty, double_buffer, interpolation, mirror, palette, prelude::*, redraw,
+    schedule, synchronization, system,
+};
 
 use bevy::{
-    app::MainScheduleOrder,
     diagnostic::{Diagnostic, RegisterDiagnostic},
-    ecs::{schedule::SingleThreadedExecutor, system::SystemState, world::World},
+    ecs::{
+        schedule::{MultiThreadedExecutor, ScheduleLabel, SingleThreadedExecutor},
+        system::SystemState,
+        world::World,
+    },
     prelude::*,
-    window::{PrimaryWindow, WindowBackendScaleFactorChanged, WindowResized},
+    window::{
+        PrimaryWindow, RequestRedraw, WindowBackendScaleFactorChanged, WindowOccluded,
+        WindowResized,
+    },
 };
 
 /// A [`Plugin`] that defines an integration between Bevy and the [`pixels`](https://github.com/parasyte/pixels)
 /// crate. Should be added to app after [`DefaultPlugins`].
+///
+/// Configure the plugin with its builder methods, starting from [`PixelsPlugin::default`].
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_pixels::prelude::*;
+/// let plugin = PixelsPlugin::default()
+///     .with_primary_window(None)
+///     .with_pipelined_rendering(true);
+/// ```
 pub struct PixelsPlugin {
     /// Configuration for the primary window pixel buffer. This will automatically create a
     /// [`PixelsWrapper`] component (using the provided options) for the primary window entity.
     pub primary_window: Option<PixelsOptions>,
+    /// GPU adapter and device options shared by all window pixel buffers.
+    pub gpu: GpuOptions,
+    /// Present frames on a background task while the next frame is simulated, see
+    /// [`pipelined`](crate::pipelined) for the ordering guarantees. Only used with the `render`
+    /// feature and ignored on the web.
+    pub pipelined_rendering: bool,
+    /// Where the [`Draw`] schedule runs. Defaults to every frame after [`PostUpdate`].
+    pub draw_placement: SchedulePlacement,
+    /// Where the [`Render`] schedule runs. The redraw decision and pipelined frame hand-back
+    /// happen in [`PrepareRedraw`], before the schedule [`Draw`] runs after, so keep [`Render`]
+    /// after it.
+    ///
+    /// `None` follows [`draw_placement`](Self::draw_placement): every frame after [`Draw`],
+    /// after [`PostUpdate`] when [`Draw`] runs in the fixed timestep, and manual when [`Draw`] is.
+    pub render_placement: Option<SchedulePlacement>,
+    /// Executor used for the [`Draw`] schedule.
+    pub draw_executor: DrawExecutor,
 }
 
 fn insert_primary_window_options(world: &mut World, options: PixelsOptions) {
@@ -34,27 +70,178 @@ impl Default for PixelsPlugin {
     fn default() -> Self {
         PixelsPlugin {
             primary_window: Some(PixelsOptions::default()),
+            gpu: GpuOptions::default(),
+            pipelined_rendering: false,
+            draw_placement: SchedulePlacement::after(PostUpdate),
+            render_placement: None,
+            draw_executor: DrawExecutor::default(),
         }
     }
 }
 
+impl PixelsPlugin {
+    /// Set [`primary_window`](Self::primary_window), or `None` to not create a buffer for the
+    /// primary window.
+    pub fn with_primary_window(mut self, options: Option<PixelsOptions>) -> Self {
+        self.primary_window = options;
+        self
+    }
+
+    /// Set [`gpu`](Self::gpu).
+    pub fn with_gpu(mut self, gpu: GpuOptions) -> Self {
+        self.gpu = gpu;
+        self
+    }
+
+    /// Set [`pipelined_rendering`](Self::pipelined_rendering).
+    pub fn with_pipelined_rendering(mut self, pipelined_rendering: bool) -> Self {
+        self.pipelined_rendering = pipelined_rendering;
+        self
+    }
+
+    /// Set [`draw_placement`](Self::draw_placement).
+    pub fn with_draw_placement(mut self, placement: SchedulePlacement) -> Self {
+        self.draw_placement = placement;
+        self
+    }
+
+    /// Set [`render_placement`](Self::render_placement).
+    pub fn with_render_placement(mut self, placement: SchedulePlacement) -> Self {
+        self.render_placement = Some(placement);
+        self
+    }
+
+    /// Set [`draw_executor`](Self::draw_executor).
+    pub fn with_draw_executor(mut self, executor: DrawExecutor) -> Self {
+        self.draw_executor = executor;
+        self
+    }
+
+    /// [`render_placement`](Self::render_placement), or the placement following
+    /// [`draw_placement`](Self::draw_placement) when unset.
+    fn effective_render_placement(&self) -> SchedulePlacement {
+        self.render_placement.unwrap_or(match self.draw_placement {
+            SchedulePlacement::After(_) => SchedulePlacement::after(Draw),
+            SchedulePlacement::FixedAfter(_) => SchedulePlacement::after(PostUpdate),
+            SchedulePlacement::Manual => SchedulePlacement::Manual,
+        })
+    }
+}
+
 impl Plugin for PixelsPlugin {
     fn build(&self, app: &mut App) {
+        let mut prepare_schedule = Schedule::new(PrepareRedraw);
+        prepare_schedule.set_executor(SingleThreadedExecutor::new());
+        // Frames lent to pipelined render tasks must be back before anything draws.
+        prepare_schedule
+            .add_systems((crate::pipelined::receive_frames, redraw::decide_redraw).chain());
+
         let mut draw_schedule = Schedule::new(Draw);
-        draw_schedule.set_executor(SingleThreadedExecutor::new());
+        match self.draw_executor {
+            DrawExecutor::MultiThreaded if cfg!(not(target_arch = "wasm32")) => {
+                draw_schedule.set_executor(MultiThreadedExecutor::new())
+            }
+            _ => draw_schedule.set_executor(SingleThreadedExecutor::new()),
+        };
+        draw_schedule.configure_sets(RedrawSet.run_if(redraw::redraw_this_frame));
+        draw_schedule.configure_sets(
+            (
+                DrawSet::Clear,
+                DrawSet::Background,
+                DrawSet::World,
+                DrawSet::Overlay,
+                DrawSet::Ui,
+                DrawSet::PostProcess,
+            )
+                .chain()
+                .in_set(RedrawSet),
+        );
 
         let mut render_schedule = Schedule::new(Render);
         render_schedule.set_executor(SingleThreadedExecutor::new());
+        render_schedule.configure_sets(RedrawSet.run_if(redraw::redraw_this_frame));
+        render_schedule.add_systems(
+            (
+                palette::resolve_palettes,
+                mirror::mirror_sources,
+                dirty::track_dirty_frames,
+                double_buffer::store_previous_frames,
+            )
+                .chain()
+                .in_set(RedrawSet),
+        );
         #[cfg(feature = "render")]
-        render_schedule.add_systems(system::render);
+        if self.pipelined_rendering && cfg!(not(target_arch = "wasm32")) {
+            // The wrapper is lent out until the next `PrepareRedraw`, so present mode changes made
+            // during `PreUpdate` must be applied here.
+            render_schedule.add_systems(
+                (
+                    synchronization::synchronize_lent_present_mode,
+                    crate::pipelined::submit_frames,
+                )
+                    .chain()
+                    .after(double_buffer::store_previous_frames)
+                    .in_set(RedrawSet),
+            );
+        } else {
+            render_schedule.add_systems(
+                system::render
+                    .after(double_buffer::store_previous_frames)
+                    .in_set(RedrawSet),
+            );
+        }
 
-        app.register_diagnostic(Diagnostic::new(diagnostic::RENDER_TIME).with_suffix("ms"))
+        let gpu = self.gpu.clone().with_env();
+        info!(
+            backends = ?gpu.backends,
+            power_preference = ?gpu.power_preference,
+            force_fallback_adapter = gpu.force_fallback_adapter,
+            custom_limits = gpu.required_limits.is_some(),
+            "pixels GPU options"
+        );
+
+        app.insert_resource(gpu)
+            .register_diagnostic(Diagnostic::new(diagnostic::RENDER_TIME).with_suffix("ms"))
+            .register_type::<PixelsOptions>()
+            .register_type::<CrtSettings>()
+            .register_type::<InterpolatedPosition>()
+            .register_type::<PixelsRedrawPolicy>()
+            .register_type::<FixedInterpolation>()
+            .init_resource::<PixelsRedrawPolicy>()
+            .init_resource::<FixedInterpolation>()
+            .init_resource::<redraw::RedrawThisFrame>()
+            .add_message::<RequestRedraw>()
             .add_message::<WindowResized>()
+            .add_message::<WindowOccluded>()
             .add_message::<WindowBackendScaleFactorChanged>()
+            .add_schedule(prepare_schedule)
             .add_schedule(draw_schedule)
             .add_schedule(render_schedule)
-            .add_systems(First, system::create_pixels)
-            .add_systems(PreUpdate, synchronization::synchronize);
+            .add_systems(
+                First,
+                (
+                    system::create_pixels,
+                    system::create_buffers,
+                    palette::create_indexed_frames,
+                ),
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    synchronization::synchronize::<PixelsWrapper>,
+                    synchronization::synchronize_present_mode,
+                    synchronization::synchronize_buffers,
+                    palette::synchronize_indexed_frames,
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    crt::synchronize_crt_settings,
+                    palette::cycle_palettes,
+                    interpolation::update_fixed_interpolation,
+                ),
+            );
 
         #[cfg(target_arch = "wasm32")]
         app.add_systems(
@@ -62,10 +249,16 @@ impl Plugin for PixelsPlugin {
             system::finish_pixels_initialization.after(system::create_pixels),
         );
 
-        // Ensure `Draw` and `Render` schedules execute at the correct moment.
-        let mut order = app.world_mut().resource_mut::<MainScheduleOrder>();
-        order.insert_after(PostUpdate, Draw);
-        order.insert_after(Draw, Render);
+        // Ensure `Draw` and `Render` schedules execute at the configured moment, after the redraw
+        // is prepared once per frame.
+        let render_placement = self.effective_render_placement();
+        let prepare_before = [self.draw_placement, render_placement]
+            .into_iter()
+            .find_map(SchedulePlacement::main_anchor)
+            .unwrap_or(PostUpdate.intern());
+        schedule::insert_before(app.world_mut(), PrepareRedraw, prepare_before);
+        self.draw_placement.insert(app.world_mut(), Draw);
+        render_placement.insert(app.world_mut(), Render);
 
         // If supplied, attach the primary window [`PixelsOptions`] component to the [`Window`]
         // entity with the [`PrimaryWindow`] marker component (if it exists). This will trigger
@@ -79,7 +272,10 @@ impl Plugin for PixelsPlugin {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use bevy::ecs::{message::Messages, schedule::ScheduleLabel};
+    use bevy::{
+        app::{FixedMainScheduleOrder, MainScheduleOrder},
+        ecs::{message::Messages, schedule::ScheduleLabel},
+    };
 
     fn schedule_index(order: &MainScheduleOrder, label: impl ScheduleLabel) -> usize {
         order
@@ -124,12 +320,102 @@ mod tests {
         app.add_plugins(PixelsPlugin::default());
 
         let order = app.world().resource::<MainScheduleOrder>();
-        let post_update = schedule_index(&order, PostUpdate);
-        let draw = schedule_index(&order, Draw);
-        let render = schedule_index(&order, Render);
+        let post_update = schedule_index(order, PostUpdate);
+        let draw = schedule_index(order, Draw);
+        let render = schedule_index(order, Render);
 
         assert_eq!(draw, post_update + 1);
         assert_eq!(render, draw + 1);
+        assert_eq!(schedule_index(order, PrepareRedraw) + 1, post_update);
+    }
+
+    #[test]
+    fn plugin_inserts_draw_into_fixed_schedule_order() {
+        let mut app = App::new();
+        app.add_plugins(PixelsPlugin {
+            draw_placement: SchedulePlacement::fixed_after(FixedPostUpdate),
+            ..default()
+        });
+
+        let fixed = &app.world().resource::<FixedMainScheduleOrder>().labels;
+        let fixed_post_update = fixed
+            .iter()
+            .position(|label| (**label).eq(&FixedPostUpdate));
+        let draw = fixed.iter().position(|label| (**label).eq(&Draw));
+        assert_eq!(draw, fixed_post_update.map(|index| index + 1));
+
+        let order = app.world().resource::<MainScheduleOrder>();
+        assert!(!order.labels.contains(&Draw.intern()));
+        assert_eq!(
+            schedule_index(order, Render),
+            schedule_index(order, PostUpdate) + 1
+        );
+        assert_eq!(
+            schedule_index(order, PrepareRedraw) + 1,
+            schedule_index(order, RunFixedMainLoop)
+        );
+    }
+
+    #[test]
+    fn manual_placement_skips_schedule_insertion() {
+        let mut app = App::new();
+        app.add_plugins(PixelsPlugin {
+            draw_placement: SchedulePlacement::Manual,
+            ..default()
+        });
+
+        let order = app.world().resource::<MainScheduleOrder>();
+        assert!(!order.labels.contains(&Draw.intern()));
+        assert!(!order.labels.contains(&Render.intern()));
+        assert!(app.get_schedule(Draw).is_some());
+        assert!(app.get_schedule(Render).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "which is not in its schedule order")]
+    fn placement_after_missing_schedule_panics() {
+        let mut app = App::new();
+        app.add_plugins(PixelsPlugin {
+            draw_placement: SchedulePlacement::Manual,
+            render_placement: Some(SchedulePlacement::after(Draw)),
+            ..default()
+        });
+    }
+
+    #[test]
+    fn render_placement_follows_draw_placement() {
+        let mut app = App::new();
+        app.add_plugins(PixelsPlugin {
+            draw_placement: SchedulePlacement::after(Update),
+            ..default()
+        });
+
+        let order = app.world().resource::<MainScheduleOrder>();
+        let draw = schedule_index(order, Draw);
+        assert_eq!(draw, schedule_index(order, Update) + 1);
+        assert_eq!(schedule_index(order, Render), draw + 1);
+        assert_eq!(
+            schedule_index(order, PrepareRedraw) + 1,
+            schedule_index(order, Update)
+        );
+    }
+
+    #[test]
+    fn plugin_registers_reflected_types() {
+        let mut app = App::new();
+        app.add_plugins(PixelsPlugin::default());
+
+        let registry = app.world().resource::<AppTypeRegistry>().read();
+        let options = registry
+            .get(std::any::TypeId::of::<PixelsOptions>())
+            .expect("options should be registered");
+        assert!(options.data::<ReflectComponent>().is_some());
+        assert!(registry.contains(std::any::TypeId::of::<ScalingMode>()));
+        assert!(
+            registry
+                .get_type_data::<ReflectResource>(std::any::TypeId::of::<PixelsRedrawPolicy>())
+                .is_some()
+        );
     }
 
     #[test]
@@ -159,17 +445,43 @@ mod tests {
             width: 320,
             height: 240,
             scale_factor: 2.0,
+            min_size: None,
+            max_size: None,
             auto_resize_buffer: false,
             auto_resize_surface: false,
+            scaling_mode: ScalingMode::Fill,
+            pixel_format: FrameFormat::Bgra8,
+            texture_color_space: Some(ColorSpace::Srgb),
+            surface_color_space: Some(ColorSpace::Srgb),
         };
 
         app.add_plugins(PixelsPlugin {
             primary_window: Some(options),
+            ..default()
         });
 
         assert_eq!(app.world().get::<PixelsOptions>(window), Some(&options));
     }
 
+    #[test]
+    fn plugin_inserts_gpu_options_resource() {
+        let mut app = App::new();
+        let gpu = GpuOptions {
+            force_fallback_adapter: true,
+            ..default()
+        };
+
+        app.add_plugins(PixelsPlugin {
+            gpu: gpu.clone(),
+            ..default()
+        });
+
+        assert_eq!(
+            app.world().resource::<GpuOptions>().force_fallback_adapter,
+            gpu.force_fallback_adapter
+        );
+    }
+
     #[test]
     fn plugin_skips_primary_window_insertion_when_disabled() {
         let mut app = App::new();
@@ -180,6 +492,7 @@ mod tests {
 
         app.add_plugins(PixelsPlugin {
             primary_window: None,
+            ..default()
         });
 
         assert!(app.world().get::<PixelsOptions>(window).is_none());
@@ -0,0 +1,552 @@
+use bevy::prelude::*;
+use pixels::{PixelsContext, wgpu};
+use std::{any::Any, error::Error};
+
+/// Error returned by a failing [`PostProcessPass`].
+pub type PassError = Box<dyn Error + Send + Sync + 'static>;
+
+/// Textures given to a [`PostProcessPass`].
+#[derive(Debug, Copy, Clone)]
+pub struct PassTextures<'a> {
+    /// The pixel buffer texture for the first pass, the output of the previous pass otherwise.
+    pub input: &'a wgpu::TextureView,
+    /// The render target, either an intermediate texture or the window surface for the last pass.
+    pub output: &'a wgpu::TextureView,
+}
+
+/// A GPU render pass in a window's [`PostProcessChain`].
+pub trait PostProcessPass: Any + wgpu::WasmNotSendSync {
+    /// Record this pass, reading `textures.input` and writing `textures.output`.
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        textures: PassTextures,
+        context: &PixelsContext,
+    ) -> Result<(), PassError>;
+}
+
+/// Scales the pixel buffer to the render target with the window's
+/// [`scaling_mode`](crate::PixelsOptions::scaling_mode). This is what the default render system
+/// does without a chain.
+///
+/// This pass always reads the pixel buffer texture, not `textures.input`, so it must be the first
+/// pass of a chain. [`PostProcessChain::render`] fails for chains with it anywhere else, and the
+/// default render systems then render without the chain.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ScalingPass;
+
+impl PostProcessPass for ScalingPass {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        textures: PassTextures,
+        context: &PixelsContext,
+    ) -> Result<(), PassError> {
+        context.scaling_renderer.render(encoder, textures.output);
+        Ok(())
+    }
+}
+
+/// Copies the input texture to the render target unchanged, stretched to fit.
+#[derive(Debug, Default)]
+pub struct PassthroughPass {
+    pipeline: Option<FullscreenPipeline>,
+}
+
+impl PostProcessPass for PassthroughPass {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        textures: PassTextures,
+        context: &PixelsContext,
+    ) -> Result<(), PassError> {
+        FullscreenPipeline::ensure(
+            &mut self.pipeline,
+            &context.device,
+            "bevy_pixels_passthrough",
+            include_str!("shaders/passthrough.wgsl"),
+            textures.output.texture().format(),
+            wgpu::FilterMode::Nearest,
+            0,
+        )
+        .render(encoder, &context.device, &context.queue, textures, &[]);
+        Ok(())
+    }
+}
+
+/// Ordered GPU passes run by the default render system for a window. Each pass reads the output of
+/// the previous one and the last pass writes to the window surface. Without this component (or
+/// with an empty chain) the buffer is rendered as by [`ScalingPass`] alone.
+///
+/// When the chain fails to render, the default render systems log the error once and render the
+/// buffer without the chain until another pass is pushed.
+#[derive(Component, Default)]
+pub struct PostProcessChain {
+    passes: Vec<Box<dyn PostProcessPass>>,
+    targets: Option<IntermediateTargets>,
+    /// Set by the render systems after the chain failed to render.
+    pub(crate) failed: bool,
+}
+
+#[cfg(target_arch = "wasm32")]
+// See `PixelsWrapper`, passes hold thread-affine `wgpu` types on wasm and only run on the browser
+// main thread.
+unsafe impl Send for PostProcessChain {}
+
+#[cfg(target_arch = "wasm32")]
+unsafe impl Sync for PostProcessChain {}
+
+impl PostProcessChain {
+    /// Create an empty chain.
+    pub fn new() -> Self {
+        PostProcessChain::default()
+    }
+
+    /// Append a pass to the end of the chain.
+    pub fn with(mut self, pass: impl PostProcessPass) -> Self {
+        self.push(pass);
+        self
+    }
+
+    /// Append a pass to the end of the chain.
+    pub fn push(&mut self, pass: impl PostProcessPass) {
+        self.passes.push(Box::new(pass));
+        self.failed = false;
+    }
+
+    /// Number of passes in the chain.
+    pub fn len(&self) -> usize {
+        self.passes.len()
+    }
+
+    /// Get the first pass of type `T`.
+    pub fn pass<T: PostProcessPass>(&self) -> Option<&T> {
+        self.passes
+            .iter()
+            .find_map(|pass| (pass.as_ref() as &dyn Any).downcast_ref())
+    }
+
+    /// Get the first pass of type `T` mutably, for example to update its parameters.
+    pub fn pass_mut<T: PostProcessPass>(&mut self) -> Option<&mut T> {
+        self.passes
+            .iter_mut()
+            .find_map(|pass| (pass.as_mut() as &mut dyn Any).downcast_mut())
+    }
+
+    /// Does the chain have no passes?
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    /// Record every pass, finishing on `surface`. Intended to be called from
+    /// [`Pixels::render_with`](pixels::Pixels::render_with).
+    pub fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        surface: &wgpu::TextureView,
+        context: &PixelsContext,
+    ) -> Result<(), PassError> {
+        self.check_order()?;
+        let buffer = context
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let count = self.passes.len();
+        let targets = if count > 1 {
+            Some(IntermediateTargets::ensure(
+                &mut self.targets,
+                &context.device,
+                surface.texture(),
+            ))
+        } else {
+            None
+        };
+
+        for (index, pass) in self.passes.iter_mut().enumerate() {
+            let input = match pass_input(index) {
+                PassInput::Buffer => &buffer,
+                PassInput::Intermediate(target) => {
+                    &targets.expect("chain has targets").views[target]
+                }
+            };
+            let output = match pass_output(index, count) {
+                PassOutput::Surface => surface,
+                PassOutput::Intermediate(target) => {
+                    &targets.expect("chain has targets").views[target]
+                }
+            };
+            pass.render(encoder, PassTextures { input, output }, context)?;
+        }
+
+        Ok(())
+    }
+
+    /// Ensure [`ScalingPass`], which ignores its input, only appears first.
+    pub(crate) fn check_order(&self) -> Result<(), PassError> {
+        let misplaced = self
+            .passes
+            .iter()
+            .skip(1)
+            .any(|pass| (pass.as_ref() as &dyn Any).is::<ScalingPass>());
+        if misplaced {
+            return Err(
+                "`ScalingPass` reads the pixel buffer, not the previous pass, and must be \
+                 the first pass of a `PostProcessChain`"
+                    .into(),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PassInput {
+    Buffer,
+    Intermediate(usize),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PassOutput {
+    Surface,
+    Intermediate(usize),
+}
+
+fn pass_input(index: usize) -> PassInput {
+    match index {
+        0 => PassInput::Buffer,
+        _ => PassInput::Intermediate((index - 1) % 2),
+    }
+}
+
+fn pass_output(index: usize, count: usize) -> PassOutput {
+    if index + 1 == count {
+        PassOutput::Surface
+    } else {
+        PassOutput::Intermediate(index % 2)
+    }
+}
+
+/// Pair of surface-sized textures the chain ping-pongs between.
+struct IntermediateTargets {
+    size: wgpu::Extent3d,
+    format: wgpu::TextureFormat,
+    views: [wgpu::TextureView; 2],
+}
+
+impl IntermediateTargets {
+    fn ensure<'a>(
+        targets: &'a mut Option<IntermediateTargets>,
+        device: &wgpu::Device,
+        surface: &wgpu::Texture,
+    ) -> &'a IntermediateTargets {
+        if targets.as_ref().is_none_or(|targets| {
+            targets.size != surface.size() || targets.format != surface.format()
+        }) {
+            let create_view = |label| {
+                device
+                    .create_texture(&wgpu::TextureDescriptor {
+                        label: Some(label),
+                        size: surface.size(),
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format: surface.format(),
+                        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                            | wgpu::TextureUsages::TEXTURE_BINDING,
+                        view_formats: &[],
+                    })
+                    .create_view(&wgpu::TextureViewDescriptor::default())
+            };
+            *targets = Some(IntermediateTargets {
+                size: surface.size(),
+                format: surface.format(),
+                views: [
+                    create_view("bevy_pixels_post_process_target_0"),
+                    create_view("bevy_pixels_post_process_target_1"),
+                ],
+            });
+        }
+
+        targets.as_ref().expect("targets were just created")
+    }
+}
+
+/// Full-screen triangle pipeline sampling the input texture, with an optional uniform buffer at
+/// binding 2. Shared by the built-in passes.
+#[derive(Debug)]
+pub(crate) struct FullscreenPipeline {
+    format: wgpu::TextureFormat,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniform_buffer: Option<wgpu::Buffer>,
+}
+
+impl FullscreenPipeline {
+    /// Get the pipeline in `slot`, (re)creating it when missing or when the target format changed.
+    /// `fragment_source` is appended to the shared full-screen vertex shader.
+    pub(crate) fn ensure<'a>(
+        slot: &'a mut Option<FullscreenPipeline>,
+        device: &wgpu::Device,
+        label: &str,
+        fragment_source: &str,
+        format: wgpu::TextureFormat,
+        filter: wgpu::FilterMode,
+        uniform_size: u64,
+    ) -> &'a FullscreenPipeline {
+        if slot
+            .as_ref()
+            .is_none_or(|pipeline| pipeline.format != format)
+        {
+            *slot = Some(FullscreenPipeline::new(
+                device,
+                label,
+                fragment_source,
+                format,
+                filter,
+                uniform_size,
+            ));
+        }
+
+        slot.as_ref().expect("pipeline was just created")
+    }
+
+    fn new(
+        device: &wgpu::Device,
+        label: &str,
+        fragment_source: &str,
+        format: wgpu::TextureFormat,
+        filter: wgpu::FilterMode,
+        uniform_size: u64,
+    ) -> Self {
+        let source = [include_str!("shaders/fullscreen.wgsl"), fragment_source].join("\n");
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(label),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::MipmapFilterMode::Nearest,
+            ..default()
+        });
+
+        let mut entries = vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ];
+        let uniform_buffer = (uniform_size > 0).then(|| {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: wgpu::BufferSize::new(uniform_size),
+                },
+                count: None,
+            });
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: uniform_size,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &entries,
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts: &[Some(&bind_group_layout)],
+            immediate_size: 0,
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview_mask: None,
+            cache: None,
+        });
+
+        FullscreenPipeline {
+            format,
+            pipeline,
+            bind_group_layout,
+            sampler,
+            uniform_buffer,
+        }
+    }
+
+    /// Draw the input texture to the output with this pipeline, uploading `uniforms` first.
+    pub(crate) fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        textures: PassTextures,
+        uniforms: &[u8],
+    ) {
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(textures.input),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&self.sampler),
+            },
+        ];
+        if let Some(uniform_buffer) = &self.uniform_buffer {
+            queue.write_buffer(uniform_buffer, 0, uniforms);
+            entries.push(wgpu::BindGroupEntry {
+                binding: 2,
+                resource: uniform_buffer.as_entire_binding(),
+            });
+        }
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.bind_group_layout,
+            entries: &entries,
+        });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: textures.output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Parse and validate a fragment shader joined with the shared full-screen vertex shader.
+    pub(crate) fn validate_fragment_shader(fragment_source: &str) {
+        use wgpu::naga::{
+            front::wgsl,
+            valid::{Capabilities, ValidationFlags, Validator},
+        };
+
+        let source = [include_str!("shaders/fullscreen.wgsl"), fragment_source].join("\n");
+        let module = wgsl::parse_str(&source).expect("shader should parse");
+        Validator::new(ValidationFlags::all(), Capabilities::empty())
+            .validate(&module)
+            .expect("shader should validate");
+    }
+
+    #[test]
+    fn passthrough_shader_is_valid() {
+        validate_fragment_shader(include_str!("shaders/passthrough.wgsl"));
+    }
+
+    #[test]
+    fn single_pass_reads_buffer_and_writes_surface() {
+        assert_eq!(pass_input(0), PassInput::Buffer);
+        assert_eq!(pass_output(0, 1), PassOutput::Surface);
+    }
+
+    #[test]
+    fn passes_ping_pong_between_intermediate_targets() {
+        let count = 4;
+        let routes: Vec<_> = (0..count)
+            .map(|index| (pass_input(index), pass_output(index, count)))
+            .collect();
+
+        assert_eq!(
+            routes,
+            vec![
+                (PassInput::Buffer, PassOutput::Intermediate(0)),
+                (PassInput::Intermediate(0), PassOutput::Intermediate(1)),
+                (PassInput::Intermediate(1), PassOutput::Intermediate(0)),
+                (PassInput::Intermediate(0), PassOutput::Surface),
+            ]
+        );
+    }
+
+    #[test]
+    fn chain_keeps_passes_in_order_added() {
+        let mut chain = PostProcessChain::new().with(ScalingPass);
+        assert!(!chain.is_empty());
+
+        chain.push(PassthroughPass::default());
+
+        assert_eq!(chain.len(), 2);
+        assert!(PostProcessChain::default().is_empty());
+    }
+
+    #[test]
+    fn passes_can_be_found_by_type() {
+        let mut chain = PostProcessChain::new().with(ScalingPass);
+
+        assert!(chain.pass::<ScalingPass>().is_some());
+        assert!(chain.pass_mut::<PassthroughPass>().is_none());
+    }
+
+    #[test]
+    fn scaling_pass_must_come_first() {
+        let first = PostProcessChain::new()
+            .with(ScalingPass)
+            .with(PassthroughPass::default());
+        let later = PostProcessChain::new()
+            .with(PassthroughPass::default())
+            .with(ScalingPass);
+
+        assert!(first.check_order().is_ok());
+        let error = later.check_order().unwrap_err().to_string();
+        assert!(error.contains("must be the first pass"), "{error}");
+    }
+}
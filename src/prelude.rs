@@ -1,4 +1,8 @@
 pub use crate::{
-    PixelsOptions, PixelsPlugin, PixelsWrapper,
-    schedule::{Draw, Render},
+    ColorSpace, CrtMask, CrtPass, CrtSettings, CycleRange, DirtyRects, DrawTarget,
+    FixedInterpolation, FrameFormat, GpuOptions, IndexedFrame, InterpolatedPosition, Palette,
+    PaletteCycle, PaletteFormat, PassthroughPass, PixelFormat, PixelsBuffer, PixelsOptions,
+    PixelsPlugin, PixelsRedrawPolicy, PixelsSource, PixelsViewports, PixelsWrapper,
+    PostProcessChain, PostProcessPass, PreviousFrame, ScalingMode, ScalingPass, Upscaler, Viewport,
+    schedule::{Draw, DrawExecutor, DrawSet, PrepareRedraw, RedrawSet, Render, SchedulePlacement},
 };
@@ -0,0 +1,82 @@
+use crate::prelude::*;
+
+use bevy::{
+    platform::time::Instant,
+    prelude::*,
+    window::{RequestRedraw, WindowResized},
+};
+use std::time::Duration;
+
+/// Controls how often the systems in [`RedrawSet`], which includes every [`DrawSet`] and the
+/// built-in render systems, run. The decision is made once per frame in [`PrepareRedraw`], so
+/// [`RequestRedraw`] messages should be written in [`Update`] or earlier.
+///
+/// Skipping a redraw leaves every pixel buffer showing its last rendered frame. Combine
+/// [`OnDemand`](PixelsRedrawPolicy::OnDemand) with `WinitSettings::desktop_app()` so idle tools
+/// also stop running the rest of the app.
+#[derive(Resource, Reflect, Debug, Default, Copy, Clone, PartialEq)]
+#[reflect(Resource, Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum PixelsRedrawPolicy {
+    /// Redraw every frame.
+    #[default]
+    Continuous,
+    /// Redraw only on frames with a [`RequestRedraw`] message, a resized window or changed or new
+    /// pixel buffers. Systems that change what is drawn, including palette cycling, must write
+    /// [`RequestRedraw`] for the change to be shown.
+    OnDemand,
+    /// Redraw at most this many times per second. Zero, negative and non-finite values do not
+    /// limit redraws, like [`Continuous`](PixelsRedrawPolicy::Continuous).
+    MaxFps(f32),
+}
+
+/// Whether the current frame redraws, decided in [`PrepareRedraw`].
+#[derive(Resource, Debug)]
+pub(crate) struct RedrawThisFrame(pub bool);
+
+impl Default for RedrawThisFrame {
+    fn default() -> Self {
+        RedrawThisFrame(true)
+    }
+}
+
+/// Decide whether [`RedrawSet`] runs this frame. Runs in [`PrepareRedraw`].
+#[allow(clippy::type_complexity)]
+pub(crate) fn decide_redraw(
+    policy: Res<PixelsRedrawPolicy>,
+    mut requests: MessageReader<RequestRedraw>,
+    mut resized: MessageReader<WindowResized>,
+    changed: Query<
+        (),
+        Or<(
+            Changed<PixelsOptions>,
+            Added<PixelsWrapper>,
+            Added<PixelsBuffer>,
+        )>,
+    >,
+    mut last_redraw: Local<Option<Instant>>,
+    mut redraw_this_frame: ResMut<RedrawThisFrame>,
+) {
+    let requested = requests.read().count() > 0;
+    let resized = resized.read().count() > 0;
+
+    let redraw = match *policy {
+        PixelsRedrawPolicy::Continuous => true,
+        PixelsRedrawPolicy::OnDemand => requested || resized || !changed.is_empty(),
+        PixelsRedrawPolicy::MaxFps(fps) if fps.is_finite() && fps > 0.0 => {
+            // Rates too low to represent never redraw again.
+            let interval = Duration::try_from_secs_f32(fps.recip()).unwrap_or(Duration::MAX);
+            last_redraw.is_none_or(|last| last.elapsed() >= interval)
+        }
+        PixelsRedrawPolicy::MaxFps(_) => true,
+    };
+    if redraw {
+        *last_redraw = Some(Instant::now());
+    }
+    redraw_this_frame.0 = redraw;
+}
+
+/// Run condition of [`RedrawSet`].
+pub(crate) fn redraw_this_frame(redraw: Res<RedrawThisFrame>) -> bool {
+    redraw.0
+}
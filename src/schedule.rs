@@ -1,7 +1,151 @@
-use bevy::ecs::schedule::ScheduleLabel;
+use bevy::{
+    app::{FixedMainScheduleOrder, MainScheduleOrder, RunFixedMainLoop},
+    ecs::{
+        schedule::{InternedScheduleLabel, ScheduleLabel, SystemSet},
+        world::World,
+    },
+};
 
 #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Draw;
 
 #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Render;
+
+/// Runs once per frame before [`Draw`] and [`Render`]. Waits for pipelined render tasks (see
+/// [`pipelined`](crate::pipelined)) and decides whether [`RedrawSet`] runs this frame, following
+/// the [`PixelsRedrawPolicy`](crate::PixelsRedrawPolicy).
+///
+/// Inserted directly before the schedule [`Draw`] runs after, or before [`RunFixedMainLoop`] when
+/// [`Draw`] runs in the fixed timestep.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PrepareRedraw;
+
+/// System set in [`Draw`] and [`Render`] that only runs on frames that redraw, see
+/// [`PixelsRedrawPolicy`](crate::PixelsRedrawPolicy). Every [`DrawSet`] and the built-in render
+/// systems are part of it. Systems added to [`Draw`] or [`Render`] outside of this set run every
+/// time their schedule runs.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RedrawSet;
+
+/// Well-known system sets configured inside the [`Draw`] schedule. The sets are chained, so they
+/// always run in declaration order: [`DrawSet::Clear`] first and [`DrawSet::PostProcess`] last.
+///
+/// Systems added to [`Draw`] without a set are unordered relative to these sets.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DrawSet {
+    /// Clear the buffer before anything else is drawn.
+    Clear,
+    /// Draw backgrounds, skies and other content behind the world.
+    Background,
+    /// Draw the game world.
+    World,
+    /// Draw content on top of the world, such as particles or debug shapes.
+    Overlay,
+    /// Draw user interface elements.
+    Ui,
+    /// Apply CPU effects to the finished frame.
+    PostProcess,
+}
+
+/// Where [`PixelsPlugin`](crate::PixelsPlugin) runs the [`Draw`] or [`Render`] schedule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SchedulePlacement {
+    /// Run once per frame, directly after this schedule in [`MainScheduleOrder`].
+    After(InternedScheduleLabel),
+    /// Run once per fixed timestep, directly after this schedule in [`FixedMainScheduleOrder`].
+    FixedAfter(InternedScheduleLabel),
+    /// Do not insert the schedule. Run it with [`World::run_schedule`], for example from a system
+    /// in a custom schedule.
+    Manual,
+}
+
+impl SchedulePlacement {
+    /// Run once per frame, directly after `label`.
+    pub fn after(label: impl ScheduleLabel) -> Self {
+        SchedulePlacement::After(label.intern())
+    }
+
+    /// Run once per fixed timestep, directly after `label`.
+    pub fn fixed_after(label: impl ScheduleLabel) -> Self {
+        SchedulePlacement::FixedAfter(label.intern())
+    }
+
+    /// Insert `schedule` into the schedule order selected by this placement.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the anchor schedule is not part of the selected schedule order.
+    pub(crate) fn insert(self, world: &mut World, schedule: impl ScheduleLabel) {
+        let (labels, after) = match self {
+            SchedulePlacement::After(after) => (
+                &mut world
+                    .resource_mut::<MainScheduleOrder>()
+                    .into_inner()
+                    .labels,
+                after,
+            ),
+            SchedulePlacement::FixedAfter(after) => (
+                &mut world
+                    .resource_mut::<FixedMainScheduleOrder>()
+                    .into_inner()
+                    .labels,
+                after,
+            ),
+            SchedulePlacement::Manual => return,
+        };
+        let index = labels
+            .iter()
+            .position(|label| *label == after)
+            .unwrap_or_else(|| {
+                panic!(
+                    "cannot run {schedule:?} after {after:?}, which is not in its schedule order"
+                )
+            });
+        labels.insert(index + 1, schedule.intern());
+    }
+
+    /// Schedule in [`MainScheduleOrder`] that a schedule placed here runs within or after. Fixed
+    /// placements run within [`RunFixedMainLoop`].
+    pub(crate) fn main_anchor(self) -> Option<InternedScheduleLabel> {
+        match self {
+            SchedulePlacement::After(after) => Some(after),
+            SchedulePlacement::FixedAfter(_) => Some(RunFixedMainLoop.intern()),
+            SchedulePlacement::Manual => None,
+        }
+    }
+}
+
+/// Insert `schedule` into [`MainScheduleOrder`] directly before `before`.
+///
+/// # Panics
+///
+/// Panics when `before` is not part of [`MainScheduleOrder`].
+pub(crate) fn insert_before(
+    world: &mut World,
+    schedule: impl ScheduleLabel,
+    before: InternedScheduleLabel,
+) {
+    let labels = &mut world
+        .resource_mut::<MainScheduleOrder>()
+        .into_inner()
+        .labels;
+    let index = labels
+        .iter()
+        .position(|label| *label == before)
+        .unwrap_or_else(|| {
+            panic!("cannot run {schedule:?} before {before:?}, which is not in its schedule order")
+        });
+    labels.insert(index, schedule.intern());
+}
+
+/// Executor used for the [`Draw`] schedule.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DrawExecutor {
+    /// Run draw systems one at a time on the main thread.
+    #[default]
+    SingleThreaded,
+    /// Run draw systems with disjoint access in parallel on the `ComputeTaskPool`. Falls back to
+    /// [`DrawExecutor::SingleThreaded`] on the web.
+    MultiThreaded,
+}
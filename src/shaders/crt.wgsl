@@ -0,0 +1,74 @@
+struct CrtUniforms {
+    // Area of the output covered by the scaled buffer: x, y, width, height.
+    image_rect: vec4<f32>,
+    buffer_size: vec2<f32>,
+    scanline_intensity: f32,
+    curvature: f32,
+    vignette: f32,
+    bloom: f32,
+    mask_intensity: f32,
+    // 0: none, 1: aperture grille, 2: slot mask, 3: shadow mask.
+    mask_type: u32,
+}
+
+@group(0) @binding(2) var<uniform> crt: CrtUniforms;
+
+const PI: f32 = 3.14159265;
+
+fn curve(uv: vec2<f32>) -> vec2<f32> {
+    let centered = uv * 2.0 - 1.0;
+    let offset = centered.yx * centered.yx * crt.curvature * 0.25;
+    return (centered + centered * offset) * 0.5 + 0.5;
+}
+
+fn mask(position: vec2<f32>) -> vec3<f32> {
+    let column = u32(position.x) % 3u;
+    let row = u32(position.y);
+    var weights = vec3<f32>(1.0);
+    switch crt.mask_type {
+        case 1u: {
+            weights = vec3<f32>(0.0);
+            weights[column] = 1.0;
+        }
+        case 2u: {
+            weights = vec3<f32>(0.0);
+            weights[column] = 1.0;
+            if ((row + (u32(position.x) / 3u) * 2u) % 4u == 0u) {
+                weights = vec3<f32>(0.0);
+            }
+        }
+        case 3u: {
+            weights = vec3<f32>(0.0);
+            weights[(column + row % 2u) % 3u] = 1.0;
+        }
+        default: {}
+    }
+    return mix(vec3<f32>(1.0), weights, crt.mask_intensity);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = curve(in.tex_coord);
+    if (any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0))) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    let output_size = vec2<f32>(textureDimensions(r_tex_color));
+    let texel = 1.5 / output_size;
+    var color = textureSampleLevel(r_tex_color, r_tex_sampler, uv, 0.0).rgb;
+    let glow = textureSampleLevel(r_tex_color, r_tex_sampler, uv + vec2<f32>(texel.x, 0.0), 0.0).rgb
+        + textureSampleLevel(r_tex_color, r_tex_sampler, uv - vec2<f32>(texel.x, 0.0), 0.0).rgb
+        + textureSampleLevel(r_tex_color, r_tex_sampler, uv + vec2<f32>(0.0, texel.y), 0.0).rgb
+        + textureSampleLevel(r_tex_color, r_tex_sampler, uv - vec2<f32>(0.0, texel.y), 0.0).rgb;
+    color = color + glow * 0.25 * crt.bloom;
+
+    let position = uv * output_size;
+    let row_height = crt.image_rect.w / max(crt.buffer_size.y, 1.0);
+    let row = fract((position.y - crt.image_rect.y) / max(row_height, 1.0));
+    let scanline = mix(1.0, sin(row * PI), crt.scanline_intensity);
+
+    let edges = uv * (1.0 - uv.yx);
+    let vignette = mix(1.0, clamp(pow(edges.x * edges.y * 16.0, 0.25), 0.0, 1.0), crt.vignette);
+
+    return vec4<f32>(color * scanline * vignette * mask(position), 1.0);
+}
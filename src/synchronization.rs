@@ -1,27 +1,105 @@
-use crate::prelude::*;
+use crate::{OptionsError, pipelined::RenderInFlight, prelude::*, system};
 
 use bevy::{
-    log::warn,
+    ecs::component::Mutable,
+    log::{error, info, warn},
     prelude::*,
     window::{WindowBackendScaleFactorChanged, WindowResized},
 };
+use pixels::TextureError;
 use std::collections::HashSet;
 
+/// Window pixel buffer resized by [`synchronize`]. Implemented by [`PixelsWrapper`]; tests use a
+/// stand-in, as creating a wrapper needs a GPU and a window.
+pub(crate) trait ResizablePixels: Component<Mutability = Mutable> {
+    /// Largest supported texture width or height.
+    fn max_texture_dimension(&self) -> u32;
+
+    /// Resize the window surface.
+    fn resize_surface(&mut self, width: u32, height: u32) -> Result<(), TextureError>;
+
+    /// Resize the pixel buffer, recreating its texture with `scaling_mode`.
+    fn resize_buffer(
+        &mut self,
+        scaling_mode: ScalingMode,
+        width: u32,
+        height: u32,
+    ) -> Result<(), TextureError>;
+}
+
+impl ResizablePixels for PixelsWrapper {
+    fn max_texture_dimension(&self) -> u32 {
+        self.pixels.device().limits().max_texture_dimension_2d
+    }
+
+    fn resize_surface(&mut self, width: u32, height: u32) -> Result<(), TextureError> {
+        self.pixels.resize_surface(width, height)
+    }
+
+    fn resize_buffer(
+        &mut self,
+        scaling_mode: ScalingMode,
+        width: u32,
+        height: u32,
+    ) -> Result<(), TextureError> {
+        // Recreating the buffer texture also applies the scaling mode to the renderer.
+        self.pixels.set_scaling_mode(scaling_mode.into());
+        self.pixels.resize_buffer(width, height)
+    }
+}
+
 #[derive(Default)]
-struct DesiredSizes {
+pub(crate) struct DesiredSizes {
     buffer: Option<(u32, u32)>,
     surface: Option<(u32, u32)>,
 }
 
+impl DesiredSizes {
+    /// Combine with sizes desired later, which take precedence.
+    fn merge(&mut self, later: DesiredSizes) {
+        self.buffer = later.buffer.or(self.buffer);
+        self.surface = later.surface.or(self.surface);
+    }
+
+    /// Whether nothing needs to be resized.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.buffer.is_none() && self.surface.is_none()
+    }
+
+    /// Resize the surface and buffer of `wrapper`. The wrapper is only marked changed when
+    /// something is resized, so unchanged windows are not redrawn.
+    pub(crate) fn apply<P: ResizablePixels>(
+        self,
+        entity: Entity,
+        options: &PixelsOptions,
+        wrapper: &mut Mut<P>,
+    ) {
+        if let Some((width, height)) = self.surface
+            && let Err(error) = wrapper.resize_surface(width, height)
+        {
+            warn!(?entity, %error, "failed to synchronize pixel surface size");
+        }
+
+        if let Some((width, height)) = self.buffer
+            && let Err(error) = wrapper.resize_buffer(options.scaling_mode, width, height)
+        {
+            warn!(?entity, %error, "failed to synchronize pixel buffer size");
+        }
+    }
+}
+
 /// Synchronize caller-selected and window-derived sizes with each window's pixel buffer.
-pub fn synchronize(
+#[allow(clippy::type_complexity)]
+pub(crate) fn synchronize<P: ResizablePixels>(
     mut window_resized: MessageReader<WindowResized>,
     mut scale_factor_changed: MessageReader<WindowBackendScaleFactorChanged>,
     mut windows: Query<(
         Entity,
         &mut PixelsOptions,
         &Window,
-        Option<&mut PixelsWrapper>,
+        Option<Mut<P>>,
+        Option<&mut RenderInFlight>,
+        Has<PixelsSource>,
     )>,
 ) {
     let resized: HashSet<Entity> = window_resized.read().map(|event| event.window).collect();
@@ -31,44 +109,269 @@ pub fn synchronize(
         .chain(resized.iter().copied())
         .collect();
 
-    for (entity, mut options, window, wrapper) in &mut windows {
+    for (entity, mut options, window, wrapper, in_flight, mirrored) in &mut windows {
         let mut desired = DesiredSizes::default();
         let wrapper_added = wrapper.as_ref().is_some_and(|wrapper| wrapper.is_added());
+        let max_dimension = wrapper
+            .as_ref()
+            .map(|wrapper| wrapper.max_texture_dimension());
 
-        if resized.contains(&entity) && options.auto_resize_buffer {
-            let (width, height) = buffer_size_for_window(window, options.scale_factor);
-            options.width = width;
-            options.height = height;
-            desired.buffer = Some((width, height));
+        // Mirrored buffers always match their source, see `PixelsSource`.
+        if resized.contains(&entity) && options.auto_resize_buffer && !mirrored {
+            match buffer_size_for_window(window, &options, max_dimension) {
+                Ok((width, height)) => {
+                    options.width = width;
+                    options.height = height;
+                    desired.buffer = Some((width, height));
+                }
+                Err(error) => error!(?entity, %error, "failed to size pixel buffer for window"),
+            }
         } else if options.is_changed() || wrapper_added {
-            desired.buffer = Some((options.width, options.height));
+            match options.validate_with_max_dimension(max_dimension) {
+                Ok(()) => desired.buffer = Some((options.width, options.height)),
+                Err(error) => {
+                    error!(?entity, %error, "invalid pixels options, keeping buffer size")
+                }
+            }
         }
 
         if (surface_changed.contains(&entity) || wrapper_added) && options.auto_resize_surface {
             desired.surface = Some((window.physical_width(), window.physical_height()));
         }
 
-        let Some(mut wrapper) = wrapper else {
+        if desired.is_empty() {
             continue;
-        };
-
-        if let Some((width, height)) = desired.surface
-            && let Err(error) = wrapper.pixels.resize_surface(width, height)
-        {
-            warn!(?entity, %error, "failed to synchronize pixel surface size");
         }
+        match (wrapper, in_flight) {
+            (Some(mut wrapper), _) => desired.apply(entity, &options, &mut wrapper),
+            // Applied when the pipelined render returns the wrapper.
+            (None, Some(mut in_flight)) => in_flight.deferred.merge(desired),
+            (None, None) => {}
+        }
+    }
+}
 
-        if let Some((width, height)) = desired.buffer
-            && let Err(error) = wrapper.pixels.resize_buffer(width, height)
-        {
-            warn!(?entity, %error, "failed to synchronize pixel buffer size");
+/// Reconfigure window surfaces whose present mode differs from [`Window::present_mode`]. Modes
+/// the surface does not support fall back to `AutoVsync` or `AutoNoVsync`, and the effective mode
+/// is reported by [`Pixels::present_mode`](pixels::Pixels::present_mode).
+pub fn synchronize_present_mode(
+    mut windows: Query<(Entity, &Window, &mut PixelsWrapper), Changed<Window>>,
+) {
+    for (entity, window, mut wrapper) in &mut windows {
+        apply_present_mode(entity, window, &mut wrapper);
+    }
+}
+
+/// Unfiltered [`synchronize_present_mode`] for pipelined rendering. Wrappers are lent to the
+/// render task during `PreUpdate`, so window changes made then would otherwise be missed.
+#[cfg(feature = "render")]
+pub(crate) fn synchronize_lent_present_mode(
+    mut windows: Query<(Entity, &Window, &mut PixelsWrapper)>,
+) {
+    for (entity, window, mut wrapper) in &mut windows {
+        apply_present_mode(entity, window, &mut wrapper);
+    }
+}
+
+fn apply_present_mode(entity: Entity, window: &Window, wrapper: &mut Mut<PixelsWrapper>) {
+    let requested = system::pixels_present_mode(window.present_mode);
+    let effective = system::effective_present_mode(
+        requested,
+        &wrapper.pixels.context().surface_capabilities.present_modes,
+    );
+    if effective == wrapper.pixels.present_mode() {
+        return;
+    }
+
+    if effective != requested {
+        warn!(
+            ?entity,
+            ?requested,
+            ?effective,
+            "present mode is not supported by the surface, falling back"
+        );
+    }
+    wrapper.pixels.set_present_mode(effective);
+    info!(?entity, present_mode = ?effective, "changed present mode");
+}
+
+/// Resize offscreen [`PixelsBuffer`]s whose [`PixelsOptions`] dimensions changed.
+pub fn synchronize_buffers(
+    mut buffers: Query<(Entity, &PixelsOptions, &mut PixelsBuffer), Changed<PixelsOptions>>,
+) {
+    for (entity, options, mut buffer) in &mut buffers {
+        if let Err(error) = options.validate() {
+            error!(?entity, %error, "invalid pixels options, keeping buffer size");
+        } else if (buffer.width(), buffer.height()) != (options.width, options.height) {
+            buffer.resize(options.width, options.height);
         }
     }
 }
 
-fn buffer_size_for_window(window: &Window, scale_factor: f32) -> (u32, u32) {
-    (
-        (window.width() / scale_factor).floor() as u32,
-        (window.height() / scale_factor).floor() as u32,
-    )
+/// Buffer size for `window` at the [effective scale
+/// factor](PixelsOptions::effective_scale_factor) of `options`, clamped to the size limits and at
+/// most `max_dimension`.
+fn buffer_size_for_window(
+    window: &Window,
+    options: &PixelsOptions,
+    max_dimension: Option<u32>,
+) -> Result<(u32, u32), OptionsError> {
+    let scale_factor = options.scale_factor;
+    if !scale_factor.is_finite() || scale_factor <= 0.0 {
+        return Err(OptionsError::InvalidScaleFactor(scale_factor));
+    }
+    let logical_size = Vec2::new(window.width(), window.height());
+    let scale_factor = options.effective_scale_factor(logical_size);
+    let (min_width, min_height) = options.min_size.unwrap_or((1, 1));
+    let (max_width, max_height) = options.max_size.unwrap_or((u32::MAX, u32::MAX));
+    let max_dimension = max_dimension.unwrap_or(u32::MAX);
+    // Rounding can miss the limits by a pixel, and an empty window gives a NaN scale factor.
+    let size = |logical: f32, min: u32, max: u32| {
+        ((logical / scale_factor).floor() as u32)
+            .min(max)
+            .max(min.max(1))
+            .min(max_dimension)
+    };
+    Ok((
+        size(logical_size.x, min_width, max_width),
+        size(logical_size.y, min_height, max_height),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::window::WindowResolution;
+
+    #[derive(Component, Default)]
+    struct StandIn {
+        resizes: u32,
+    }
+
+    impl ResizablePixels for StandIn {
+        fn max_texture_dimension(&self) -> u32 {
+            8192
+        }
+
+        fn resize_surface(&mut self, _width: u32, _height: u32) -> Result<(), TextureError> {
+            self.resizes += 1;
+            Ok(())
+        }
+
+        fn resize_buffer(
+            &mut self,
+            _scaling_mode: ScalingMode,
+            _width: u32,
+            _height: u32,
+        ) -> Result<(), TextureError> {
+            self.resizes += 1;
+            Ok(())
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct ChangedThisFrame(bool);
+
+    #[test]
+    fn unchanged_windows_do_not_mark_the_wrapper_changed() {
+        let mut app = App::new();
+        app.add_message::<WindowResized>()
+            .add_message::<WindowBackendScaleFactorChanged>()
+            .init_resource::<ChangedThisFrame>()
+            .add_systems(Update, synchronize::<StandIn>)
+            .add_systems(
+                Last,
+                |wrapper: Single<Ref<StandIn>>, mut changed: ResMut<ChangedThisFrame>| {
+                    changed.0 = wrapper.is_changed();
+                },
+            );
+        let entity = app
+            .world_mut()
+            .spawn((
+                PixelsOptions::default(),
+                window(640, 360),
+                StandIn::default(),
+            ))
+            .id();
+
+        // The new wrapper is sized once.
+        app.update();
+        assert_eq!(app.world().get::<StandIn>(entity).unwrap().resizes, 2);
+
+        app.update();
+        app.update();
+        assert_eq!(app.world().get::<StandIn>(entity).unwrap().resizes, 2);
+        assert!(!app.world().resource::<ChangedThisFrame>().0);
+    }
+
+    fn window(width: u32, height: u32) -> Window {
+        Window {
+            resolution: WindowResolution::new(width, height),
+            ..default()
+        }
+    }
+
+    fn scaled(scale_factor: f32) -> PixelsOptions {
+        PixelsOptions {
+            scale_factor,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn window_buffer_size_divides_by_scale_factor() {
+        assert_eq!(
+            buffer_size_for_window(&window(640, 360), &scaled(2.0), None),
+            Ok((320, 180))
+        );
+    }
+
+    #[test]
+    fn window_buffer_size_is_clamped() {
+        assert_eq!(
+            buffer_size_for_window(&window(0, 0), &scaled(1.0), None),
+            Ok((1, 1))
+        );
+        assert_eq!(
+            buffer_size_for_window(&window(4096, 100), &scaled(1.0), Some(2048)),
+            Ok((2048, 100))
+        );
+    }
+
+    #[test]
+    fn window_buffer_size_respects_size_limits() {
+        let options = PixelsOptions {
+            min_size: Some((320, 180)),
+            max_size: Some((640, 360)),
+            ..scaled(1.0)
+        };
+
+        assert_eq!(
+            buffer_size_for_window(&window(3840, 2160), &options, None),
+            Ok((640, 360))
+        );
+        assert_eq!(
+            buffer_size_for_window(&window(160, 90), &options, None),
+            Ok((320, 180))
+        );
+        // Wider than the limits allow: the width is capped and the height keeps the scale.
+        assert_eq!(
+            buffer_size_for_window(&window(1920, 180), &options, None),
+            Ok((640, 180))
+        );
+        assert_eq!(
+            buffer_size_for_window(&window(0, 0), &options, None),
+            Ok((320, 180))
+        );
+    }
+
+    #[test]
+    fn window_buffer_size_rejects_invalid_scale_factors() {
+        for scale_factor in [0.0, -1.0, f32::NAN] {
+            assert!(matches!(
+                buffer_size_for_window(&window(640, 360), &scaled(scale_factor), None),
+                Err(OptionsError::InvalidScaleFactor(_))
+            ));
+        }
+    }
 }
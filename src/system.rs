@@ -1,13 +1,17 @@
 #[cfg(feature = "render")]
 #[cfg(not(target_arch = "wasm32"))]
 use crate::diagnostic;
-use crate::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::format::linear_surface_format;
+use crate::{DirtyRects, TextureFormats, pipelined::RenderInFlight, prelude::*};
 
 #[cfg(feature = "render")]
 #[cfg(not(target_arch = "wasm32"))]
 use bevy::diagnostic::Diagnostics;
 #[cfg(target_arch = "wasm32")]
 use bevy::tasks::{AsyncComputeTaskPool, Task, futures::check_ready};
+#[cfg(feature = "render")]
+use bevy::window::WindowOccluded;
 use bevy::{
     ecs::system::NonSendMarker,
     prelude::*,
@@ -15,6 +19,9 @@ use bevy::{
 };
 use pixels::{PixelsBuilder, SurfaceTexture};
 #[cfg(feature = "render")]
+use std::collections::HashSet;
+use std::fmt;
+#[cfg(feature = "render")]
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
 
@@ -22,7 +29,7 @@ use std::time::Instant;
 #[derive(Component)]
 pub(crate) struct PendingPixels(Task<Result<PixelsWrapper, pixels::Error>>);
 
-fn pixels_present_mode(present_mode: PresentMode) -> pixels::wgpu::PresentMode {
+pub(crate) fn pixels_present_mode(present_mode: PresentMode) -> pixels::wgpu::PresentMode {
     match present_mode {
         PresentMode::Fifo => pixels::wgpu::PresentMode::Fifo,
         PresentMode::FifoRelaxed => pixels::wgpu::PresentMode::FifoRelaxed,
@@ -33,32 +40,190 @@ fn pixels_present_mode(present_mode: PresentMode) -> pixels::wgpu::PresentMode {
     }
 }
 
+/// Present mode used for a `requested` mode given the surface's `supported` modes. Unsupported
+/// modes fall back to the automatic mode with the same vsync behavior, which every surface
+/// supports.
+pub(crate) fn effective_present_mode(
+    requested: pixels::wgpu::PresentMode,
+    supported: &[pixels::wgpu::PresentMode],
+) -> pixels::wgpu::PresentMode {
+    use pixels::wgpu::PresentMode;
+
+    match requested {
+        PresentMode::AutoVsync | PresentMode::AutoNoVsync => requested,
+        _ if supported.contains(&requested) => requested,
+        PresentMode::Immediate | PresentMode::Mailbox => PresentMode::AutoNoVsync,
+        _ => PresentMode::AutoVsync,
+    }
+}
+
+/// Validate `options` and select their texture formats, logging an error (once per change of the
+/// options) when they are invalid or unsupported so the buffer is not created.
+fn texture_formats(
+    entity: Entity,
+    options: &Ref<PixelsOptions>,
+    gpu: &GpuOptions,
+) -> Option<TextureFormats> {
+    let max_dimension = gpu
+        .required_limits
+        .as_ref()
+        .map(|limits| limits.max_texture_dimension_2d);
+    options
+        .validate_with_max_dimension(max_dimension)
+        .map_err(|error| error.to_string())
+        .and_then(|()| options.texture_formats().map_err(|error| error.to_string()))
+        .inspect_err(|error| {
+            if options.is_changed() {
+                error!(?entity, %error, "failed to create pixels");
+            }
+        })
+        .ok()
+}
+
+/// Configure a [`PixelsBuilder`] identically on native and web.
+fn pixels_builder<'win, W>(
+    options: &PixelsOptions,
+    gpu: &GpuOptions,
+    window: &Window,
+    surface_texture: SurfaceTexture<W>,
+    formats: TextureFormats,
+) -> PixelsBuilder<'static, 'static, 'win, W>
+where
+    W: pixels::wgpu::WindowHandle + pixels::raw_window_handle::HasDisplayHandle + 'win,
+{
+    let builder = PixelsBuilder::new(options.width, options.height, surface_texture)
+        .present_mode(pixels_present_mode(window.present_mode))
+        .texture_format(formats.texture)
+        .wgpu_backend(gpu.backends)
+        .request_adapter_options(gpu.request_adapter_options());
+    let builder = match gpu.device_descriptor() {
+        Some(descriptor) => builder.device_descriptor(descriptor),
+        None => builder,
+    };
+
+    match formats.surface {
+        Some(format) => builder.surface_texture_format(format),
+        None => builder,
+    }
+}
+
+/// Log the adapter selected for a new pixel buffer.
+fn log_adapter(entity: Entity, wrapper: &PixelsWrapper) {
+    let info = wrapper.pixels.adapter().get_info();
+    info!(
+        ?entity,
+        adapter = %info.name,
+        backend = ?info.backend,
+        device_type = ?info.device_type,
+        driver = %info.driver,
+        "created pixels"
+    );
+}
+
+/// Marks windows whose [`PixelsWrapper`] could not be created. Creation is retried when their
+/// [`PixelsOptions`] change.
+#[derive(Component)]
+pub(crate) struct PixelsCreationFailed;
+
+/// Insert a newly created [`PixelsWrapper`], or log why creation failed and mark the window.
+fn insert_created_pixels(
+    commands: &mut Commands,
+    entity: Entity,
+    result: Result<PixelsWrapper, impl fmt::Display>,
+) {
+    match result {
+        Ok(wrapper) => {
+            log_adapter(entity, &wrapper);
+            commands
+                .entity(entity)
+                .remove::<PixelsCreationFailed>()
+                .insert(wrapper);
+        }
+        Err(error) => {
+            error!(?entity, %error, "failed to create pixels");
+            commands.entity(entity).insert(PixelsCreationFailed);
+        }
+    }
+}
+
 /// Create [`PixelsWrapper`] (and underlying [`Pixels`] buffer) for all suitable [`Window`] with
 /// a [`PixelsOptions`] component.
 #[cfg(not(target_arch = "wasm32"))]
 #[allow(clippy::type_complexity)]
 pub fn create_pixels(
     mut commands: Commands,
-    query: Query<(Entity, &PixelsOptions, &Window, &RawHandleWrapper), Without<PixelsWrapper>>,
+    query: Query<
+        (
+            Entity,
+            Ref<PixelsOptions>,
+            &Window,
+            &RawHandleWrapper,
+            Has<PixelsCreationFailed>,
+        ),
+        (Without<PixelsWrapper>, Without<RenderInFlight>),
+    >,
+    gpu: Res<GpuOptions>,
     _main_thread: NonSendMarker,
 ) {
-    for (entity, options, window, raw_handle_wrapper) in &query {
-        // SAFETY: `NonSendMarker` forces this system onto Bevy's main thread, which is required by
-        // `RawHandleWrapper::get_handle` on platforms whose window handles are thread-affine.
-        let thread_locked_handle = unsafe { raw_handle_wrapper.get_handle() };
+    for (entity, options, window, raw_handle_wrapper, failed) in &query {
+        if failed && !options.is_changed() {
+            continue;
+        }
+        let Some(formats) = texture_formats(entity, &options, &gpu) else {
+            continue;
+        };
 
-        let surface_texture = SurfaceTexture::new(
-            window.physical_width(),
-            window.physical_height(),
-            thread_locked_handle,
-        );
+        let build = |surface: Option<pixels::wgpu::TextureFormat>| {
+            // SAFETY: `NonSendMarker` forces this system onto Bevy's main thread, which is
+            // required by `RawHandleWrapper::get_handle` on platforms whose window handles are
+            // thread-affine.
+            let thread_locked_handle = unsafe { raw_handle_wrapper.get_handle() };
+            let surface_texture = SurfaceTexture::new(
+                window.physical_width(),
+                window.physical_height(),
+                thread_locked_handle,
+            );
+            let formats = TextureFormats { surface, ..formats };
+            // Fails instead of panicking, for example when the buffer exceeds the adapter's
+            // limits.
+            pixels_builder(&options, &gpu, window, surface_texture, formats)
+                .build()
+                .map_err(|error| error.to_string())
+        };
 
-        let pixels = PixelsBuilder::new(options.width, options.height, surface_texture)
-            .present_mode(pixels_present_mode(window.present_mode))
-            .build()
-            .expect("failed to create pixels");
+        // `pixels` prefers sRGB surfaces. A linear surface is only known from the capabilities
+        // of the built surface, so the buffer is rebuilt with it when necessary.
+        let result = build(formats.surface)
+            .and_then(|pixels| {
+                if !formats.linear_surface || !pixels.surface_texture_format().is_srgb() {
+                    return Ok(pixels);
+                }
+                let surface = linear_surface_format(&pixels.context().surface_capabilities.formats)
+                    .map_err(|error| error.to_string())?;
+                drop(pixels);
+                build(Some(surface))
+            })
+            .map(|pixels| PixelsWrapper { pixels });
+        insert_created_pixels(&mut commands, entity, result);
+    }
+}
 
-        commands.entity(entity).insert(PixelsWrapper { pixels });
+/// Create [`PixelsBuffer`] for all entities with a [`PixelsOptions`] component but no [`Window`].
+#[allow(clippy::type_complexity)]
+pub fn create_buffers(
+    mut commands: Commands,
+    query: Query<(Entity, Ref<PixelsOptions>), (Without<Window>, Without<PixelsBuffer>)>,
+) {
+    for (entity, options) in &query {
+        if let Err(error) = options.validate() {
+            if options.is_changed() {
+                error!(?entity, %error, "failed to create pixel buffer");
+            }
+            continue;
+        }
+        commands
+            .entity(entity)
+            .insert(PixelsBuffer::new(options.width, options.height));
     }
 }
 
@@ -68,12 +233,26 @@ pub fn create_pixels(
 pub fn create_pixels(
     mut commands: Commands,
     query: Query<
-        (Entity, &PixelsOptions, &Window, &RawHandleWrapper),
+        (
+            Entity,
+            Ref<PixelsOptions>,
+            &Window,
+            &RawHandleWrapper,
+            Has<PixelsCreationFailed>,
+        ),
         (Without<PixelsWrapper>, Without<PendingPixels>),
     >,
+    gpu: Res<GpuOptions>,
     _main_thread: NonSendMarker,
 ) {
-    for (entity, options, window, raw_handle_wrapper) in &query {
+    for (entity, options, window, raw_handle_wrapper, failed) in &query {
+        if failed && !options.is_changed() {
+            continue;
+        }
+        let Some(formats) = texture_formats(entity, &options, &gpu) else {
+            continue;
+        };
+
         // SAFETY: `NonSendMarker` forces this system onto Bevy's main thread. The spawned local
         // task remains on the browser thread required by the thread-affine window handle.
         let thread_locked_handle = unsafe { raw_handle_wrapper.get_handle() };
@@ -82,10 +261,7 @@ pub fn create_pixels(
             window.physical_height(),
             thread_locked_handle,
         );
-        let builder = PixelsBuilder::new(options.width, options.height, surface_texture)
-            .present_mode(pixels_present_mode(window.present_mode))
-            .texture_format(pixels::wgpu::TextureFormat::Rgba8Unorm)
-            .surface_texture_format(pixels::wgpu::TextureFormat::Rgba8Unorm);
+        let builder = pixels_builder(&options, &gpu, window, surface_texture, formats);
         let task = AsyncComputeTaskPool::get().spawn_local(async move {
             builder
                 .build_async()
@@ -105,27 +281,54 @@ pub fn finish_pixels_initialization(
 ) {
     for (entity, mut pending) in &mut query {
         if let Some(result) = check_ready(&mut pending.0) {
-            let wrapper = result.expect("failed to create pixels");
-            commands
-                .entity(entity)
-                .remove::<PendingPixels>()
-                .insert(wrapper);
+            commands.entity(entity).remove::<PendingPixels>();
+            insert_created_pixels(&mut commands, entity, result);
         }
     }
 }
 
 /// Render buffer to surface.
+///
+/// Windows are only presented when their frame may have changed: the [`PixelsWrapper`] was
+/// mutably accessed, [`DirtyRects`] are pending, the [`PostProcessChain`] changed or the window
+/// became visible again. Call `set_changed` on the chain to keep animated passes presenting.
 #[cfg(feature = "render")]
+#[allow(clippy::type_complexity)]
 pub fn render(
     // TODO: Support `RENDER_TIME` diagnostics on web.
     #[cfg(not(target_arch = "wasm32"))] mut diagnostics: Diagnostics,
-    query: Query<&PixelsWrapper>,
+    mut occluded: MessageReader<WindowOccluded>,
+    mut query: Query<(
+        Entity,
+        Ref<PixelsWrapper>,
+        Option<&mut PostProcessChain>,
+        Option<&mut DirtyRects>,
+    )>,
 ) {
     #[cfg(not(target_arch = "wasm32"))]
     let start = Instant::now();
 
-    for wrapper in &query {
-        wrapper.pixels.render().expect("failed to render pixels");
+    let revealed = revealed_windows(&mut occluded);
+
+    for (entity, wrapper, mut chain, dirty) in &mut query {
+        let changed = frame_changed(
+            wrapper.is_changed(),
+            dirty.as_deref(),
+            chain.as_ref().is_some_and(|chain| chain.is_changed()),
+        );
+        if !changed && !revealed.contains(&entity) {
+            continue;
+        }
+
+        // Rendering only updates cached targets, which must not count as a change of the chain.
+        render_frame(
+            &wrapper,
+            chain.as_mut().map(|chain| chain.bypass_change_detection()),
+        );
+
+        if let Some(mut dirty) = dirty {
+            dirty.clear();
+        }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -136,10 +339,150 @@ pub fn render(
     }
 }
 
+/// Present one window frame, through its post-process chain when it has passes.
+#[cfg(feature = "render")]
+pub(crate) fn render_frame(wrapper: &PixelsWrapper, chain: Option<&mut PostProcessChain>) {
+    let pixels = &wrapper.pixels;
+    render_with_fallback(
+        chain,
+        |chain| {
+            pixels.render_with(|encoder, target, context| chain.render(encoder, target, context))
+        },
+        || pixels.render(),
+    )
+    .expect("failed to render pixels");
+}
+
+/// Render through `chain` with `render_with`, or with `render` alone when the chain is empty or
+/// failed. Chain failures are logged once and fall back to `render`.
+#[cfg(feature = "render")]
+fn render_with_fallback(
+    chain: Option<&mut PostProcessChain>,
+    render_with: impl FnOnce(&mut PostProcessChain) -> Result<(), pixels::Error>,
+    render: impl FnOnce() -> Result<(), pixels::Error>,
+) -> Result<(), pixels::Error> {
+    let Some(chain) = chain.filter(|chain| !chain.is_empty() && !chain.failed) else {
+        return render();
+    };
+    // Misordered chains are rejected before the surface texture is acquired.
+    let result = chain
+        .check_order()
+        .map_err(pixels::Error::UserDefined)
+        .and_then(|()| render_with(chain));
+    match result {
+        Err(pixels::Error::UserDefined(error)) => {
+            error!(%error, "failed to render post-process chain, rendering without it");
+            chain.failed = true;
+            render()
+        }
+        result => result,
+    }
+}
+
+/// Windows that became visible again and must be presented even when unchanged.
+#[cfg(feature = "render")]
+pub(crate) fn revealed_windows(occluded: &mut MessageReader<WindowOccluded>) -> HashSet<Entity> {
+    occluded
+        .read()
+        .filter(|message| !message.occluded)
+        .map(|message| message.window)
+        .collect()
+}
+
+/// Whether a window frame may differ from the last presented frame.
+#[cfg_attr(not(feature = "render"), allow(dead_code))]
+pub(crate) fn frame_changed(
+    wrapper_changed: bool,
+    dirty: Option<&DirtyRects>,
+    chain_changed: bool,
+) -> bool {
+    wrapper_changed || chain_changed || dirty.is_some_and(|dirty| !dirty.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "render")]
+    #[test]
+    fn misordered_chains_fall_back_to_plain_render() {
+        let mut chain = PostProcessChain::new()
+            .with(PassthroughPass::default())
+            .with(ScalingPass);
+        let mut plain_renders = 0;
+
+        for _ in 0..2 {
+            render_with_fallback(
+                Some(&mut chain),
+                |_| panic!("misordered chain should not render"),
+                || {
+                    plain_renders += 1;
+                    Ok(())
+                },
+            )
+            .unwrap();
+        }
+
+        assert_eq!(plain_renders, 2);
+        assert!(chain.failed);
+        chain.push(PassthroughPass::default());
+        assert!(!chain.failed);
+    }
+
+    #[test]
+    fn failed_creation_marks_window_instead_of_panicking() {
+        let mut world = World::new();
+        let entity = world.spawn(PixelsOptions::default()).id();
+
+        let oversized = pixels::TextureError::TextureWidth(u32::MAX);
+        insert_created_pixels(
+            &mut world.commands(),
+            entity,
+            Err(pixels::Error::from(oversized)),
+        );
+        world.flush();
+
+        assert!(world.entity(entity).contains::<PixelsCreationFailed>());
+        assert!(!world.entity(entity).contains::<PixelsWrapper>());
+    }
+
+    #[test]
+    fn unchanged_frames_are_not_presented() {
+        let mut dirty = DirtyRects::default();
+
+        assert!(!frame_changed(false, None, false));
+        assert!(!frame_changed(false, Some(&dirty), false));
+        assert!(frame_changed(true, None, false));
+        assert!(frame_changed(false, None, true));
+
+        dirty.mark(URect::new(0, 0, 1, 1));
+        assert!(frame_changed(false, Some(&dirty), false));
+    }
+
+    #[test]
+    fn unsupported_present_modes_fall_back_by_vsync() {
+        use pixels::wgpu::PresentMode;
+
+        let supported = [PresentMode::Fifo, PresentMode::Mailbox];
+
+        assert_eq!(
+            effective_present_mode(PresentMode::Mailbox, &supported),
+            PresentMode::Mailbox
+        );
+        assert_eq!(
+            effective_present_mode(PresentMode::Immediate, &supported),
+            PresentMode::AutoNoVsync
+        );
+        assert_eq!(
+            effective_present_mode(PresentMode::FifoRelaxed, &supported),
+            PresentMode::AutoVsync
+        );
+        assert_eq!(
+            effective_present_mode(PresentMode::AutoNoVsync, &[]),
+            PresentMode::AutoNoVsync
+        );
+    }
+
     #[test]
     fn present_mode_mapping_matches_pixels() {
         assert_eq!(
@@ -0,0 +1,159 @@
+use crate::{DirtyRects, FormatError, ViewportFrame, buffer::blit, prelude::*};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+/// System param for drawing to the pixel buffer of the single entity marked with component `M`.
+///
+/// Systems using this param are skipped when no entity (or more than one entity) has both a
+/// [`PixelsWrapper`] and the marker component, the same as Bevy's [`Single`] system param.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_pixels::prelude::*;
+/// #[derive(Component)]
+/// struct MapWindow;
+///
+/// fn draw_map(mut target: DrawTarget<MapWindow>) {
+///     target.frame_mut().fill(0xff);
+/// }
+/// ```
+#[derive(SystemParam)]
+#[allow(clippy::type_complexity)]
+pub struct DrawTarget<'w, 's, M: Component> {
+    target: Single<
+        'w,
+        's,
+        (
+            &'static mut PixelsWrapper,
+            &'static PixelsOptions,
+            Option<&'static mut DirtyRects>,
+            Option<&'static PreviousFrame>,
+            Option<&'static PixelsViewports>,
+        ),
+        With<M>,
+    >,
+}
+
+impl<M: Component> DrawTarget<'_, '_, M> {
+    /// Get a mutable byte slice for the target pixel buffer. Marks the whole frame dirty when the
+    /// target tracks [`DirtyRects`].
+    pub fn frame_mut(&mut self) -> &mut [u8] {
+        self.target.0.pixels.frame_mut()
+    }
+
+    /// Get a mutable byte slice for the target pixel buffer, marking only `rect` dirty when the
+    /// target tracks [`DirtyRects`]. Changes outside `rect` may not be shown.
+    pub fn frame_rect_mut(&mut self, rect: URect) -> &mut [u8] {
+        let (wrapper, _, dirty, _, _) = &mut *self.target;
+        match dirty {
+            Some(dirty) => {
+                dirty.mark(rect);
+                wrapper.bypass_change_detection().pixels.frame_mut()
+            }
+            None => wrapper.pixels.frame_mut(),
+        }
+    }
+
+    /// Get an immutable byte slice for the target pixel buffer.
+    pub fn frame(&self) -> &[u8] {
+        self.target.0.pixels.frame()
+    }
+
+    /// Borrow the part of the target pixel buffer covered by `viewport`, marking only the
+    /// viewport dirty when the target tracks [`DirtyRects`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError::UnsupportedTextureFormat`] when the frame has no [`PixelFormat`].
+    pub fn viewport(&mut self, viewport: &Viewport) -> Result<ViewportFrame<'_>, FormatError> {
+        let (format, width) = self.format_and_width()?;
+        Ok(viewport.frame_with_format(self.frame_rect_mut(viewport.rect), width, format))
+    }
+
+    /// Split-screen viewports of the target, from its [`PixelsViewports`] component. Empty when the
+    /// target has none.
+    pub fn viewports(&self) -> &[Viewport] {
+        self.target.4.map_or(&[], |viewports| &viewports.0)
+    }
+
+    /// Call `draw` with the index and view of each of the target's [`PixelsViewports`], in order.
+    /// Only the viewports are marked dirty when the target tracks [`DirtyRects`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError::UnsupportedTextureFormat`] when the frame has no [`PixelFormat`].
+    pub fn for_each_viewport(
+        &mut self,
+        mut draw: impl FnMut(usize, ViewportFrame<'_>),
+    ) -> Result<(), FormatError> {
+        let (format, width) = self.format_and_width()?;
+        let (wrapper, _, dirty, _, viewports) = &mut *self.target;
+        let Some(viewports) = viewports else {
+            return Ok(());
+        };
+        for (index, viewport) in viewports.0.iter().enumerate() {
+            let frame = match dirty {
+                Some(dirty) => {
+                    dirty.mark(viewport.rect);
+                    wrapper.bypass_change_detection().pixels.frame_mut()
+                }
+                None => wrapper.pixels.frame_mut(),
+            };
+            draw(index, viewport.frame_with_format(frame, width, format));
+        }
+        Ok(())
+    }
+
+    /// Copy `buffer` into the target with its top-left corner at `position`, converting it to the
+    /// frame's [`PixelFormat`]. Pixels falling outside the target are clipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError::UnsupportedTextureFormat`] when the frame has no [`PixelFormat`].
+    pub fn blit(&mut self, buffer: &PixelsBuffer, position: IVec2) -> Result<(), FormatError> {
+        let (format, _) = self.format_and_width()?;
+        let extent = self.target.0.pixels.context().texture_extent;
+        let size = UVec2::new(buffer.width(), buffer.height());
+        let rect = URect::from_corners(
+            position.max(IVec2::ZERO).as_uvec2(),
+            (position + size.as_ivec2()).max(IVec2::ZERO).as_uvec2(),
+        );
+        blit(
+            buffer.frame(),
+            size,
+            self.frame_rect_mut(rect),
+            UVec2::new(extent.width, extent.height),
+            format,
+            position,
+        );
+        Ok(())
+    }
+
+    /// The last rendered frame, when the target has a [`PreviousFrame`].
+    pub fn previous_frame(&self) -> Option<&PreviousFrame> {
+        self.target.3
+    }
+
+    /// Options of the target pixel buffer.
+    pub fn options(&self) -> &PixelsOptions {
+        self.target.1
+    }
+
+    /// Pixel format and width of the target frame.
+    fn format_and_width(&self) -> Result<(PixelFormat, u32), FormatError> {
+        let context = self.target.0.pixels.context();
+        let format = self
+            .target
+            .0
+            .pixel_format()
+            .ok_or(FormatError::UnsupportedTextureFormat(
+                context.texture_format,
+            ))?;
+        Ok((format, context.texture_extent.width))
+    }
+
+    /// Mutable access to the target [`PixelsWrapper`].
+    pub fn wrapper_mut(&mut self) -> Mut<'_, PixelsWrapper> {
+        self.target.0.reborrow()
+    }
+}
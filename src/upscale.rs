@@ -0,0 +1,454 @@
+use crate::{
+    FormatError, PixelFormat, PixelsBuffer, PixelsWrapper, convert_pixels, format::check_frame_size,
+};
+
+use std::borrow::Cow;
+
+type Pixel = [u8; 4];
+
+/// CPU pixel-art upscaling filter used by [`upscale`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Upscaler {
+    /// Repeat each pixel `factor` times in both directions. A factor of zero is treated as one.
+    Nearest(u32),
+    /// Scale2x (AdvMAME2x), 2x edge-preserving upscaling on exact color matches.
+    Scale2x,
+    /// Scale3x (AdvMAME3x), 3x edge-preserving upscaling on exact color matches.
+    Scale3x,
+    /// Eric's Pixel Expansion, the original formulation of Scale2x. Produces the same output as
+    /// [`Upscaler::Scale2x`] and is provided for parity with references that name it EPX.
+    Epx,
+    /// Simplified hq2x. Uses hqx's YUV similarity thresholds and blends corners instead of
+    /// copying neighbours, without the full hqx pattern table.
+    Hq2xLite,
+    /// Simplified single-pass 2x xBR. Blends corners along diagonal edges whose endpoints are more
+    /// similar than the pixels across them.
+    XbrLite,
+}
+
+impl Upscaler {
+    /// Size multiplier of this upscaler.
+    pub fn factor(&self) -> u32 {
+        match self {
+            Upscaler::Nearest(factor) => (*factor).max(1),
+            Upscaler::Scale3x => 3,
+            Upscaler::Scale2x | Upscaler::Epx | Upscaler::Hq2xLite | Upscaler::XbrLite => 2,
+        }
+    }
+}
+
+/// Upscale an RGBA8 `frame` of `width` by `height` pixels into a new [`PixelsBuffer`].
+///
+/// # Errors
+///
+/// Returns [`FormatError::FrameSize`] when `frame` does not hold exactly `width` by `height`
+/// pixels.
+pub fn upscale(
+    frame: &[u8],
+    width: u32,
+    height: u32,
+    upscaler: Upscaler,
+) -> Result<PixelsBuffer, FormatError> {
+    check_frame_size(frame, PixelFormat::Rgba8, width, height)?;
+    Ok(upscale_frame(frame, width, height, upscaler))
+}
+
+/// [`upscale`] a frame known to hold `width` by `height` pixels.
+fn upscale_frame(frame: &[u8], width: u32, height: u32, upscaler: Upscaler) -> PixelsBuffer {
+    let factor = upscaler.factor();
+    let mut output = PixelsBuffer::new(width * factor, height * factor);
+    let source = Source {
+        frame,
+        width: width as i64,
+        height: height as i64,
+    };
+
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            let (block_x, block_y) = (x as u32, y as u32);
+            match upscaler {
+                Upscaler::Nearest(_) => {
+                    let block = std::iter::repeat_n(source.get(x, y), (factor * factor) as usize);
+                    write_block(&mut output, block_x, block_y, factor, block);
+                }
+                Upscaler::Scale2x => {
+                    let block = scale2x(&source.neighbours(x, y));
+                    write_block(&mut output, block_x, block_y, factor, block);
+                }
+                Upscaler::Scale3x => {
+                    let block = scale3x(&source.neighbours(x, y));
+                    write_block(&mut output, block_x, block_y, factor, block);
+                }
+                Upscaler::Epx => {
+                    let block = epx(&source.neighbours(x, y));
+                    write_block(&mut output, block_x, block_y, factor, block);
+                }
+                Upscaler::Hq2xLite => {
+                    let block = hq2x_lite(&source.neighbours(x, y));
+                    write_block(&mut output, block_x, block_y, factor, block);
+                }
+                Upscaler::XbrLite => {
+                    let block = xbr_lite(&source.neighbours(x, y));
+                    write_block(&mut output, block_x, block_y, factor, block);
+                }
+            }
+        }
+    }
+
+    output
+}
+
+impl PixelsWrapper {
+    /// Upscale the current frame on the CPU, for example to export a screenshot. Frames in other
+    /// [`PixelFormat`]s are converted to RGBA8 first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError::UnsupportedTextureFormat`] when the frame has no [`PixelFormat`].
+    pub fn upscale(&self, upscaler: Upscaler) -> Result<PixelsBuffer, FormatError> {
+        let context = self.pixels.context();
+        let format = self
+            .pixel_format()
+            .ok_or(FormatError::UnsupportedTextureFormat(
+                context.texture_format,
+            ))?;
+        let extent = context.texture_extent;
+        let frame = match format {
+            PixelFormat::Rgba8 => Cow::Borrowed(self.pixels.frame()),
+            _ => {
+                let mut rgba = vec![0; extent.width as usize * extent.height as usize * 4];
+                convert_pixels(self.pixels.frame(), format, &mut rgba, PixelFormat::Rgba8)?;
+                Cow::Owned(rgba)
+            }
+        };
+        Ok(upscale_frame(&frame, extent.width, extent.height, upscaler))
+    }
+}
+
+impl PixelsBuffer {
+    /// Upscale this buffer on the CPU into a new buffer.
+    pub fn upscale(&self, upscaler: Upscaler) -> PixelsBuffer {
+        upscale_frame(self.frame(), self.width(), self.height(), upscaler)
+    }
+}
+
+struct Source<'a> {
+    frame: &'a [u8],
+    width: i64,
+    height: i64,
+}
+
+impl Source<'_> {
+    /// Pixel at `(x, y)`, clamping coordinates to the frame edges.
+    fn get(&self, x: i64, y: i64) -> Pixel {
+        let x = x.clamp(0, self.width - 1);
+        let y = y.clamp(0, self.height - 1);
+        let offset = ((y * self.width + x) * 4) as usize;
+        self.frame[offset..offset + 4]
+            .try_into()
+            .expect("pixel is four bytes")
+    }
+
+    /// The 3x3 neighbourhood around `(x, y)` in row-major order, so `[4]` is the center pixel.
+    fn neighbours(&self, x: i64, y: i64) -> [Pixel; 9] {
+        std::array::from_fn(|index| self.get(x + index as i64 % 3 - 1, y + index as i64 / 3 - 1))
+    }
+}
+
+/// Write the `factor` by `factor` output pixels of source pixel `(x, y)`, in row-major order.
+fn write_block(
+    output: &mut PixelsBuffer,
+    x: u32,
+    y: u32,
+    factor: u32,
+    block: impl IntoIterator<Item = Pixel>,
+) {
+    let width = output.width();
+    let frame = output.frame_mut();
+    for (index, pixel) in block.into_iter().enumerate() {
+        let out_x = x * factor + index as u32 % factor;
+        let out_y = y * factor + index as u32 / factor;
+        let offset = ((out_y * width + out_x) * 4) as usize;
+        frame[offset..offset + 4].copy_from_slice(&pixel);
+    }
+}
+
+fn scale2x(&[_, b, _, d, e, f, _, h, _]: &[Pixel; 9]) -> [Pixel; 4] {
+    if b != h && d != f {
+        [
+            if d == b { d } else { e },
+            if b == f { f } else { e },
+            if d == h { d } else { e },
+            if h == f { f } else { e },
+        ]
+    } else {
+        [e; 4]
+    }
+}
+
+fn scale3x(&[a, b, c, d, e, f, g, h, i]: &[Pixel; 9]) -> [Pixel; 9] {
+    if b == h || d == f {
+        return [e; 9];
+    }
+
+    [
+        if d == b { d } else { e },
+        if (d == b && e != c) || (b == f && e != a) {
+            b
+        } else {
+            e
+        },
+        if b == f { f } else { e },
+        if (d == b && e != g) || (d == h && e != a) {
+            d
+        } else {
+            e
+        },
+        e,
+        if (b == f && e != i) || (h == f && e != c) {
+            f
+        } else {
+            e
+        },
+        if d == h { d } else { e },
+        if (d == h && e != i) || (h == f && e != g) {
+            h
+        } else {
+            e
+        },
+        if h == f { f } else { e },
+    ]
+}
+
+fn epx(&[_, a, _, c, p, b, _, d, _]: &[Pixel; 9]) -> [Pixel; 4] {
+    let neighbours = [a, b, c, d];
+    let three_match = neighbours
+        .iter()
+        .any(|pixel| neighbours.iter().filter(|other| *other == pixel).count() >= 3);
+    if three_match {
+        return [p; 4];
+    }
+
+    [
+        if c == a { a } else { p },
+        if a == b { b } else { p },
+        if d == c { c } else { p },
+        if b == d { d } else { p },
+    ]
+}
+
+fn hq2x_lite(&[_, b, _, d, e, f, _, h, _]: &[Pixel; 9]) -> [Pixel; 4] {
+    if similar(b, h) || similar(d, f) {
+        return [e; 4];
+    }
+
+    let corner = |first: Pixel, second: Pixel| {
+        if similar(first, second) && !similar(e, first) {
+            blend(e, blend(first, second, 1, 1), 1, 1)
+        } else {
+            e
+        }
+    };
+    [corner(d, b), corner(b, f), corner(d, h), corner(h, f)]
+}
+
+fn xbr_lite(&[a, b, c, d, e, f, g, h, i]: &[Pixel; 9]) -> [Pixel; 4] {
+    // A corner lies on an edge when its two orthogonal neighbours are closer to each other than
+    // the center is to the diagonal neighbour across that edge.
+    let corner = |first: Pixel, second: Pixel, across: Pixel| {
+        let along = distance(first, second);
+        if along < distance(e, across) && along < distance(e, first) {
+            blend(e, blend(first, second, 1, 1), 1, 1)
+        } else {
+            e
+        }
+    };
+    [
+        corner(d, b, a),
+        corner(b, f, c),
+        corner(d, h, g),
+        corner(h, f, i),
+    ]
+}
+
+/// Weighted blend of two pixels, per channel.
+fn blend(first: Pixel, second: Pixel, first_weight: u32, second_weight: u32) -> Pixel {
+    std::array::from_fn(|channel| {
+        ((first[channel] as u32 * first_weight + second[channel] as u32 * second_weight)
+            / (first_weight + second_weight)) as u8
+    })
+}
+
+fn yuv([r, g, b, _]: Pixel) -> [f32; 3] {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    [
+        0.299 * r + 0.587 * g + 0.114 * b,
+        -0.169 * r - 0.331 * g + 0.5 * b + 128.0,
+        0.5 * r - 0.419 * g - 0.081 * b + 128.0,
+    ]
+}
+
+/// hqx color similarity, using its luma and chroma thresholds.
+fn similar(first: Pixel, second: Pixel) -> bool {
+    let [y1, u1, v1] = yuv(first);
+    let [y2, u2, v2] = yuv(second);
+    (y1 - y2).abs() <= 48.0
+        && (u1 - u2).abs() <= 7.0
+        && (v1 - v2).abs() <= 6.0
+        && first[3].abs_diff(second[3]) <= 48
+}
+
+/// Perceptual distance between two pixels, weighting luma over chroma.
+fn distance(first: Pixel, second: Pixel) -> f32 {
+    let [y1, u1, v1] = yuv(first);
+    let [y2, u2, v2] = yuv(second);
+    48.0 * (y1 - y2).abs() + 7.0 * (u1 - u2).abs() + 6.0 * (v1 - v2).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: Pixel = [0xff, 0xff, 0xff, 0xff];
+    const K: Pixel = [0x00, 0x00, 0x00, 0xff];
+
+    fn frame(pixels: &[Pixel]) -> Vec<u8> {
+        pixels.concat()
+    }
+
+    fn pixels(buffer: &PixelsBuffer) -> Vec<Pixel> {
+        buffer
+            .frame()
+            .chunks_exact(4)
+            .map(|pixel| pixel.try_into().unwrap())
+            .collect()
+    }
+
+    /// Neighbourhood of a white pixel on the corner of a black diagonal edge.
+    #[rustfmt::skip]
+    const EDGE: [Pixel; 9] = [
+        K, K, W,
+        K, W, W,
+        W, W, W,
+    ];
+    const GRAY: Pixel = [0x7f, 0x7f, 0x7f, 0xff];
+
+    #[test]
+    fn factors_match_output_sizes() {
+        for upscaler in [
+            Upscaler::Nearest(4),
+            Upscaler::Scale2x,
+            Upscaler::Scale3x,
+            Upscaler::Epx,
+            Upscaler::Hq2xLite,
+            Upscaler::XbrLite,
+        ] {
+            let output = upscale(&frame(&[W; 6]), 3, 2, upscaler).unwrap();
+
+            assert_eq!(
+                (output.width(), output.height()),
+                (3 * upscaler.factor(), 2 * upscaler.factor())
+            );
+        }
+        assert_eq!(Upscaler::Nearest(0).factor(), 1);
+    }
+
+    #[test]
+    fn uniform_images_stay_uniform() {
+        for upscaler in [
+            Upscaler::Scale2x,
+            Upscaler::Scale3x,
+            Upscaler::Epx,
+            Upscaler::Hq2xLite,
+            Upscaler::XbrLite,
+        ] {
+            let output = upscale(&frame(&[K; 4]), 2, 2, upscaler).unwrap();
+
+            assert!(pixels(&output).iter().all(|&pixel| pixel == K));
+        }
+    }
+
+    #[test]
+    fn nearest_repeats_pixels() {
+        let output = upscale(&frame(&[W, K]), 2, 1, Upscaler::Nearest(2)).unwrap();
+
+        assert_eq!(pixels(&output), vec![W, W, K, K, W, W, K, K]);
+    }
+
+    #[test]
+    fn scale2x_fills_edge_corner() {
+        assert_eq!(scale2x(&EDGE), [K, W, W, W]);
+    }
+
+    #[test]
+    fn scale3x_fills_edge_corner() {
+        assert_eq!(scale3x(&EDGE), [K, W, W, W, W, W, W, W, W]);
+    }
+
+    #[test]
+    fn epx_matches_scale2x() {
+        #[rustfmt::skip]
+        let neighbourhoods = [
+            EDGE,
+            [K, K, K, K, W, K, K, W, K],
+            [W, K, W, K, W, W, W, W, W],
+            [W, W, W, K, W, K, W, K, W],
+        ];
+
+        for neighbourhood in neighbourhoods {
+            assert_eq!(epx(&neighbourhood), scale2x(&neighbourhood));
+        }
+    }
+
+    #[test]
+    fn epx_keeps_pixel_with_three_equal_neighbours() {
+        assert_eq!(epx(&[K, K, K, K, W, K, K, W, K]), [W; 4]);
+    }
+
+    #[test]
+    fn hq2x_lite_blends_edge_corner() {
+        assert_eq!(hq2x_lite(&EDGE), [GRAY, W, W, W]);
+    }
+
+    #[test]
+    fn hq2x_lite_treats_similar_colors_as_equal() {
+        let near_black = [0x08, 0x08, 0x08, 0xff];
+
+        assert!(similar(K, near_black));
+        assert!(!similar(K, W));
+    }
+
+    #[test]
+    fn xbr_lite_blends_edge_corner() {
+        assert_eq!(xbr_lite(&EDGE), [GRAY, W, W, W]);
+    }
+
+    #[test]
+    fn upscale_places_blocks_in_output() {
+        let output = upscale(&frame(&[K, W]), 2, 1, Upscaler::Scale2x).unwrap();
+
+        assert_eq!(pixels(&output), vec![K, K, W, W, K, K, W, W]);
+    }
+
+    #[test]
+    fn buffer_upscale_matches_free_function() {
+        let mut buffer = PixelsBuffer::new(3, 3);
+        buffer.frame_mut().copy_from_slice(&frame(&EDGE));
+
+        assert_eq!(
+            buffer.upscale(Upscaler::Scale2x),
+            upscale(&frame(&EDGE), 3, 3, Upscaler::Scale2x).unwrap()
+        );
+    }
+
+    #[test]
+    fn short_frames_are_rejected() {
+        assert_eq!(
+            upscale(&frame(&[W; 3]), 2, 2, Upscaler::Scale2x),
+            Err(FormatError::FrameSize {
+                expected: 16,
+                actual: 12,
+            })
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use crate::PixelFormat;
+
+use bevy::prelude::*;
+
+/// Rectangular region of a window's pixel buffer showing the world from its own camera. Used for
+/// split-screen rendering of several views into one buffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Viewport {
+    /// Region of the buffer covered by this viewport, in buffer pixels. `max` is exclusive.
+    pub rect: URect,
+    /// World position shown at the center of the viewport.
+    pub camera: IVec2,
+}
+
+impl Viewport {
+    /// Create a viewport covering `rect`, with the camera at the world origin.
+    pub fn new(rect: URect) -> Self {
+        Viewport {
+            rect,
+            camera: IVec2::ZERO,
+        }
+    }
+
+    /// Split a buffer of `size` into `columns` by `rows` equally sized viewports, in row-major
+    /// order. The last column and row absorb any remainder.
+    pub fn grid(size: UVec2, columns: u32, rows: u32) -> Vec<Viewport> {
+        let cell = size / UVec2::new(columns.max(1), rows.max(1));
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let min = cell * UVec2::new(column, row);
+                let max = UVec2::new(
+                    if column + 1 == columns {
+                        size.x
+                    } else {
+                        min.x + cell.x
+                    },
+                    if row + 1 == rows {
+                        size.y
+                    } else {
+                        min.y + cell.y
+                    },
+                );
+                Viewport::new(URect::from_corners(min, max))
+            })
+            .collect()
+    }
+
+    /// Borrow the part of `frame` (an RGBA8 buffer `frame_width` pixels wide) covered by this
+    /// viewport.
+    pub fn frame<'a>(&self, frame: &'a mut [u8], frame_width: u32) -> ViewportFrame<'a> {
+        self.frame_with_format(frame, frame_width, PixelFormat::Rgba8)
+    }
+
+    /// Borrow the part of `frame` (a buffer of `format` pixels, `frame_width` pixels wide) covered
+    /// by this viewport. Colors written through the view are converted to `format`.
+    pub fn frame_with_format<'a>(
+        &self,
+        frame: &'a mut [u8],
+        frame_width: u32,
+        format: PixelFormat,
+    ) -> ViewportFrame<'a> {
+        let frame_height = (frame.len() / format.bytes_per_pixel())
+            .checked_div(frame_width as usize)
+            .unwrap_or(0) as u32;
+        ViewportFrame {
+            frame,
+            frame_width,
+            format,
+            clip: self
+                .rect
+                .intersect(URect::new(0, 0, frame_width, frame_height)),
+            viewport: *self,
+        }
+    }
+}
+
+/// Split-screen viewports of a window's pixel buffer. Draw systems draw each view through
+/// [`DrawTarget::for_each_viewport`](crate::DrawTarget::for_each_viewport).
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PixelsViewports(pub Vec<Viewport>);
+
+/// Mutable view of a window's pixel buffer restricted to a [`Viewport`]. Writes are given in world
+/// coordinates, translated by the viewport camera and clipped to the viewport rect.
+#[derive(Debug)]
+pub struct ViewportFrame<'a> {
+    frame: &'a mut [u8],
+    frame_width: u32,
+    format: PixelFormat,
+    clip: URect,
+    viewport: Viewport,
+}
+
+impl ViewportFrame<'_> {
+    /// The viewport this view draws into.
+    pub fn viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+
+    /// Region of the buffer writes are clipped to. This is the viewport rect limited to the buffer.
+    pub fn clip_rect(&self) -> URect {
+        self.clip
+    }
+
+    /// World positions covered by this view, as a rect with exclusive `max`.
+    pub fn world_rect(&self) -> IRect {
+        let min = self.viewport_to_world(self.clip.min.as_ivec2());
+        IRect::from_corners(min, min + self.clip.size().as_ivec2())
+    }
+
+    /// Convert a world position to a buffer position. The result may lie outside the viewport.
+    pub fn world_to_viewport(&self, world: IVec2) -> IVec2 {
+        world - self.viewport.camera + self.viewport.rect.min.as_ivec2() + self.half_size()
+    }
+
+    /// Convert a buffer position to a world position.
+    pub fn viewport_to_world(&self, position: IVec2) -> IVec2 {
+        position - self.viewport.rect.min.as_ivec2() - self.half_size() + self.viewport.camera
+    }
+
+    /// Set the RGBA8 color of the pixel at a world position. Pixels outside the viewport are
+    /// ignored.
+    pub fn set_pixel(&mut self, world: IVec2, color: [u8; 4]) {
+        let position = self.world_to_viewport(world);
+        if position.x < self.clip.min.x as i32
+            || position.y < self.clip.min.y as i32
+            || position.x >= self.clip.max.x as i32
+            || position.y >= self.clip.max.y as i32
+        {
+            return;
+        }
+
+        let bytes_per_pixel = self.format.bytes_per_pixel();
+        let offset = (position.y as usize * self.frame_width as usize + position.x as usize)
+            * bytes_per_pixel;
+        self.format
+            .write(color, &mut self.frame[offset..offset + bytes_per_pixel]);
+    }
+
+    /// Fill the whole viewport with an RGBA8 color.
+    pub fn fill(&mut self, color: [u8; 4]) {
+        let bytes_per_pixel = self.format.bytes_per_pixel();
+        let mut encoded = [0; 8];
+        let encoded = &mut encoded[..bytes_per_pixel];
+        self.format.write(color, encoded);
+
+        for y in self.clip.min.y..self.clip.max.y {
+            let start = (y as usize * self.frame_width as usize + self.clip.min.x as usize)
+                * bytes_per_pixel;
+            let end = start + self.clip.width() as usize * bytes_per_pixel;
+            for pixel in self.frame[start..end].chunks_exact_mut(bytes_per_pixel) {
+                pixel.copy_from_slice(encoded);
+            }
+        }
+    }
+
+    fn half_size(&self) -> IVec2 {
+        self.viewport.rect.half_size().as_ivec2()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [0xff, 0x00, 0x00, 0xff];
+
+    fn pixel(frame: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+        let offset = ((y * width + x) * 4) as usize;
+        frame[offset..offset + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn grid_splits_buffer_and_absorbs_remainder() {
+        let viewports = Viewport::grid(UVec2::new(5, 4), 2, 1);
+
+        assert_eq!(
+            viewports,
+            vec![
+                Viewport::new(URect::new(0, 0, 2, 4)),
+                Viewport::new(URect::new(2, 0, 5, 4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn camera_is_centered_in_viewport() {
+        let mut frame = vec![0; 8 * 4 * 4];
+        let viewport = Viewport {
+            rect: URect::new(4, 0, 8, 4),
+            camera: IVec2::new(10, 10),
+        };
+
+        let mut view = viewport.frame(&mut frame, 8);
+        view.set_pixel(IVec2::new(10, 10), RED);
+
+        assert_eq!(pixel(&frame, 8, 6, 2), RED);
+    }
+
+    #[test]
+    fn world_and_viewport_positions_round_trip() {
+        let mut frame = vec![0; 8 * 4 * 4];
+        let viewport = Viewport {
+            rect: URect::new(4, 0, 8, 4),
+            camera: IVec2::new(-3, 7),
+        };
+        let view = viewport.frame(&mut frame, 8);
+        let world = IVec2::new(5, -2);
+
+        assert_eq!(view.viewport_to_world(view.world_to_viewport(world)), world);
+        assert_eq!(view.world_rect(), IRect::new(-5, 5, -1, 9));
+    }
+
+    #[test]
+    fn writes_are_clipped_to_viewport() {
+        let mut frame = vec![0; 8 * 4 * 4];
+        let viewport = Viewport::new(URect::new(0, 0, 4, 4));
+
+        let mut view = viewport.frame(&mut frame, 8);
+        view.set_pixel(IVec2::new(2, 0), RED);
+        view.set_pixel(IVec2::new(-3, 0), RED);
+
+        assert!(frame.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn fill_only_covers_viewport_inside_buffer() {
+        let mut frame = vec![0; 4 * 2 * 4];
+        let viewport = Viewport::new(URect::new(2, 1, 6, 3));
+
+        viewport.frame(&mut frame, 4).fill(RED);
+
+        assert_eq!(pixel(&frame, 4, 2, 1), RED);
+        assert_eq!(pixel(&frame, 4, 3, 1), RED);
+        assert_eq!(frame.iter().filter(|&&byte| byte != 0).count(), 4);
+    }
+
+    #[test]
+    fn writes_are_converted_to_frame_format() {
+        let mut frame = vec![0; 2 * 2 * 4];
+        let viewport = Viewport::new(URect::new(0, 0, 2, 2));
+
+        let mut view = viewport.frame_with_format(&mut frame, 2, PixelFormat::Bgra8);
+        view.fill([1, 2, 3, 4]);
+        view.set_pixel(IVec2::new(-1, -1), RED);
+
+        assert_eq!(frame[..4], [0x00, 0x00, 0xff, 0xff]);
+        assert_eq!(frame[4..8], [3, 2, 1, 4]);
+    }
+}
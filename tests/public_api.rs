@@ -2,7 +2,7 @@ use bevy::{
     app::MainScheduleOrder,
     ecs::message::Messages,
     prelude::*,
-    window::{PrimaryWindow, WindowBackendScaleFactorChanged, WindowResized},
+    window::{PrimaryWindow, RequestRedraw, WindowBackendScaleFactorChanged, WindowResized},
 };
 use bevy_pixels::prelude::*;
 
@@ -31,6 +31,37 @@ fn record_last(mut trace: ResMut<ExecutionTrace>) {
     trace.0.push("last");
 }
 
+fn record_clear(mut trace: ResMut<ExecutionTrace>) {
+    trace.0.push("clear");
+}
+
+fn record_background(mut trace: ResMut<ExecutionTrace>) {
+    trace.0.push("background");
+}
+
+fn record_world(mut trace: ResMut<ExecutionTrace>) {
+    trace.0.push("world");
+}
+
+fn record_overlay(mut trace: ResMut<ExecutionTrace>) {
+    trace.0.push("overlay");
+}
+
+fn record_ui(mut trace: ResMut<ExecutionTrace>) {
+    trace.0.push("ui");
+}
+
+fn record_post_process(mut trace: ResMut<ExecutionTrace>) {
+    trace.0.push("post_process");
+}
+
+#[derive(Component)]
+struct MapWindow;
+
+fn record_map_target(_target: DrawTarget<MapWindow>, mut trace: ResMut<ExecutionTrace>) {
+    trace.0.push("map_target");
+}
+
 fn count_draw(mut counts: ResMut<ScheduleCounts>) {
     counts.draw += 1;
 }
@@ -66,13 +97,17 @@ fn public_api_inserts_custom_primary_window_options() {
         width: 320,
         height: 240,
         scale_factor: 2.0,
+        min_size: None,
+        max_size: None,
         auto_resize_buffer: false,
         auto_resize_surface: false,
+        scaling_mode: ScalingMode::Fill,
+        pixel_format: FrameFormat::Bgra8,
+        texture_color_space: Some(ColorSpace::Srgb),
+        surface_color_space: Some(ColorSpace::Srgb),
     };
 
-    app.add_plugins(PixelsPlugin {
-        primary_window: Some(options),
-    });
+    app.add_plugins(PixelsPlugin::default().with_primary_window(Some(options)));
 
     assert_eq!(app.world().get::<PixelsOptions>(window), Some(&options));
 }
@@ -97,9 +132,7 @@ fn plugin_with_primary_window_none_updates_without_inserting_options() {
     let mut app = App::new();
     let window = add_primary_window(&mut app);
 
-    app.add_plugins(PixelsPlugin {
-        primary_window: None,
-    });
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
     app.update();
 
     assert!(app.world().get::<PixelsOptions>(window).is_none());
@@ -109,9 +142,7 @@ fn plugin_with_primary_window_none_updates_without_inserting_options() {
 fn draw_and_render_schedules_run_in_expected_frame_order() {
     let mut app = App::new();
     app.init_resource::<ExecutionTrace>();
-    app.add_plugins(PixelsPlugin {
-        primary_window: None,
-    });
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
     app.add_systems(PostUpdate, record_post_update);
     app.add_systems(Draw, record_draw);
     app.add_systems(Render, record_render);
@@ -125,13 +156,75 @@ fn draw_and_render_schedules_run_in_expected_frame_order() {
     );
 }
 
+#[test]
+fn pipelined_rendering_keeps_frame_order() {
+    let mut app = App::new();
+    app.init_resource::<ExecutionTrace>();
+    app.add_plugins(
+        PixelsPlugin::default()
+            .with_primary_window(None)
+            .with_pipelined_rendering(true),
+    );
+    app.add_systems(PostUpdate, record_post_update);
+    app.add_systems(Draw, record_draw);
+    app.add_systems(Render, record_render);
+    app.add_systems(Last, record_last);
+
+    app.update();
+    app.update();
+
+    assert_eq!(
+        app.world().resource::<ExecutionTrace>().0,
+        vec![
+            "post_update",
+            "draw",
+            "render",
+            "last",
+            "post_update",
+            "draw",
+            "render",
+            "last"
+        ]
+    );
+}
+
+#[test]
+fn draw_sets_run_in_chained_order() {
+    let mut app = App::new();
+    app.init_resource::<ExecutionTrace>();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    app.add_systems(
+        Draw,
+        (
+            record_post_process.in_set(DrawSet::PostProcess),
+            record_ui.in_set(DrawSet::Ui),
+            record_overlay.in_set(DrawSet::Overlay),
+            record_world.in_set(DrawSet::World),
+            record_background.in_set(DrawSet::Background),
+            record_clear.in_set(DrawSet::Clear),
+        ),
+    );
+
+    app.update();
+
+    assert_eq!(
+        app.world().resource::<ExecutionTrace>().0,
+        vec![
+            "clear",
+            "background",
+            "world",
+            "overlay",
+            "ui",
+            "post_process"
+        ]
+    );
+}
+
 #[test]
 fn draw_and_render_schedules_run_every_frame() {
     let mut app = App::new();
     app.init_resource::<ScheduleCounts>();
-    app.add_plugins(PixelsPlugin {
-        primary_window: None,
-    });
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
     app.add_systems(Draw, count_draw);
     app.add_systems(Render, count_render);
 
@@ -145,12 +238,168 @@ fn draw_and_render_schedules_run_every_frame() {
     );
 }
 
+#[test]
+fn on_demand_redraw_policy_skips_frames_without_requests() {
+    let mut app = App::new();
+    app.init_resource::<ScheduleCounts>();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    app.insert_resource(PixelsRedrawPolicy::OnDemand);
+    app.add_systems(Draw, count_draw.in_set(DrawSet::World));
+    app.add_systems(Render, count_render.in_set(RedrawSet));
+
+    app.update();
+    app.update();
+    assert_eq!(
+        *app.world().resource::<ScheduleCounts>(),
+        ScheduleCounts { draw: 0, render: 0 }
+    );
+
+    app.world_mut().write_message(RequestRedraw);
+    app.update();
+    app.update();
+    assert_eq!(
+        *app.world().resource::<ScheduleCounts>(),
+        ScheduleCounts { draw: 1, render: 1 }
+    );
+
+    app.world_mut().spawn(PixelsOptions::default());
+    app.update();
+    assert_eq!(
+        *app.world().resource::<ScheduleCounts>(),
+        ScheduleCounts { draw: 2, render: 2 }
+    );
+}
+
+#[test]
+fn systems_outside_redraw_set_run_every_frame() {
+    let mut app = App::new();
+    app.init_resource::<ScheduleCounts>();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    app.insert_resource(PixelsRedrawPolicy::OnDemand);
+    app.add_systems(Draw, count_draw);
+    app.add_systems(Render, count_render.in_set(RedrawSet));
+
+    app.update();
+    app.update();
+
+    assert_eq!(
+        *app.world().resource::<ScheduleCounts>(),
+        ScheduleCounts { draw: 2, render: 0 }
+    );
+}
+
+#[test]
+fn max_fps_redraw_policy_limits_frames() {
+    let mut app = App::new();
+    app.init_resource::<ScheduleCounts>();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    app.insert_resource(PixelsRedrawPolicy::MaxFps(0.01));
+    app.add_systems(Draw, count_draw.in_set(DrawSet::World));
+    app.add_systems(Render, count_render.in_set(RedrawSet));
+
+    app.update();
+    app.update();
+    app.update();
+
+    assert_eq!(
+        *app.world().resource::<ScheduleCounts>(),
+        ScheduleCounts { draw: 1, render: 1 }
+    );
+}
+
+#[test]
+fn non_positive_max_fps_does_not_limit_frames() {
+    for fps in [0.0, -1.0, f32::NAN] {
+        let mut app = App::new();
+        app.init_resource::<ScheduleCounts>();
+        app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+        app.insert_resource(PixelsRedrawPolicy::MaxFps(fps));
+        app.add_systems(Draw, count_draw.in_set(DrawSet::World));
+        app.add_systems(Render, count_render.in_set(RedrawSet));
+
+        app.update();
+        app.update();
+        app.update();
+
+        assert_eq!(
+            *app.world().resource::<ScheduleCounts>(),
+            ScheduleCounts { draw: 3, render: 3 }
+        );
+    }
+}
+
+#[test]
+fn manually_placed_schedules_run_when_requested() {
+    let mut app = App::new();
+    app.add_plugins(
+        PixelsPlugin::default()
+            .with_primary_window(None)
+            .with_draw_placement(SchedulePlacement::Manual)
+            .with_render_placement(SchedulePlacement::Manual),
+    )
+    .init_resource::<ScheduleCounts>()
+    .add_systems(Draw, count_draw)
+    .add_systems(Render, count_render);
+
+    app.update();
+    assert_eq!(
+        *app.world().resource::<ScheduleCounts>(),
+        ScheduleCounts::default()
+    );
+
+    app.world_mut().run_schedule(Draw);
+    app.world_mut().run_schedule(Render);
+    assert_eq!(
+        *app.world().resource::<ScheduleCounts>(),
+        ScheduleCounts { draw: 1, render: 1 }
+    );
+}
+
+#[test]
+fn multi_threaded_draw_executor_keeps_draw_set_order() {
+    let mut app = App::new();
+    app.add_plugins(
+        PixelsPlugin::default()
+            .with_primary_window(None)
+            .with_draw_executor(DrawExecutor::MultiThreaded),
+    )
+    .init_resource::<ExecutionTrace>()
+    .add_systems(
+        Draw,
+        (
+            record_ui.in_set(DrawSet::Ui),
+            record_clear.in_set(DrawSet::Clear),
+            record_world.in_set(DrawSet::World),
+        ),
+    );
+
+    app.update();
+
+    assert_eq!(
+        app.world().resource::<ExecutionTrace>().0,
+        vec!["clear", "world", "ui"]
+    );
+}
+
+#[test]
+fn fixed_interpolation_follows_fixed_time_overstep() {
+    let mut app = App::new();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    app.update();
+    assert_eq!(app.world().resource::<FixedInterpolation>().alpha, 1.0);
+
+    let mut time = Time::<Fixed>::from_seconds(1.0);
+    time.accumulate_overstep(std::time::Duration::from_millis(250));
+    app.insert_resource(time);
+    app.update();
+
+    assert_eq!(app.world().resource::<FixedInterpolation>().alpha, 0.25);
+}
+
 #[test]
 fn public_schedule_order_resource_matches_custom_schedules() {
     let mut app = App::new();
-    app.add_plugins(PixelsPlugin {
-        primary_window: None,
-    });
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
 
     let order = app.world().resource::<MainScheduleOrder>();
     let labels = &order.labels;
@@ -179,9 +428,7 @@ fn public_schedule_order_resource_matches_custom_schedules() {
 #[test]
 fn plugin_registers_window_messages_for_public_apps() {
     let mut app = App::new();
-    app.add_plugins(PixelsPlugin {
-        primary_window: None,
-    });
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
 
     assert!(app.world().contains_resource::<Messages<WindowResized>>());
     assert!(
@@ -193,9 +440,7 @@ fn plugin_registers_window_messages_for_public_apps() {
 #[test]
 fn window_resize_updates_each_automatic_buffer_size_without_a_pixels_wrapper() {
     let mut app = App::new();
-    app.add_plugins(PixelsPlugin {
-        primary_window: None,
-    });
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
 
     let automatic = app
         .world_mut()
@@ -285,3 +530,301 @@ fn window_resize_updates_each_automatic_buffer_size_without_a_pixels_wrapper() {
     let high_dpi = app.world().get::<PixelsOptions>(high_dpi).unwrap();
     assert_eq!((high_dpi.width, high_dpi.height), (320, 180));
 }
+
+#[test]
+fn draw_target_system_is_skipped_without_a_matching_pixel_buffer() {
+    let mut app = App::new();
+    app.init_resource::<ExecutionTrace>();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    app.world_mut()
+        .spawn((Window::default(), PixelsOptions::default(), MapWindow));
+    app.add_systems(Draw, record_map_target);
+
+    app.update();
+
+    assert!(app.world().resource::<ExecutionTrace>().0.is_empty());
+}
+
+#[test]
+fn window_resize_ignores_invalid_scale_factor() {
+    let mut app = App::new();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    let window = app
+        .world_mut()
+        .spawn((
+            Window {
+                resolution: bevy::window::WindowResolution::new(640, 480),
+                ..default()
+            },
+            PixelsOptions {
+                width: 320,
+                height: 240,
+                scale_factor: 0.0,
+                ..default()
+            },
+        ))
+        .id();
+
+    app.world_mut().write_message(WindowResized {
+        window,
+        width: 640.0,
+        height: 480.0,
+    });
+    app.update();
+
+    let options = app.world().get::<PixelsOptions>(window).unwrap();
+    assert_eq!((options.width, options.height), (320, 240));
+}
+
+#[test]
+fn window_resize_caps_buffer_at_max_size() {
+    let mut app = App::new();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    let window = app
+        .world_mut()
+        .spawn((
+            Window {
+                resolution: bevy::window::WindowResolution::new(3840, 2160),
+                ..default()
+            },
+            PixelsOptions {
+                max_size: Some((640, 360)),
+                ..default()
+            },
+        ))
+        .id();
+
+    app.world_mut().write_message(WindowResized {
+        window,
+        width: 3840.0,
+        height: 2160.0,
+    });
+    app.update();
+
+    let options = app.world().get::<PixelsOptions>(window).unwrap();
+    assert_eq!((options.width, options.height), (640, 360));
+    assert_eq!(
+        options.effective_scale_factor(Vec2::new(3840.0, 2160.0)),
+        6.0
+    );
+}
+
+#[test]
+fn offscreen_buffers_reject_empty_sizes() {
+    let mut app = App::new();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    let empty = app
+        .world_mut()
+        .spawn(PixelsOptions {
+            width: 0,
+            ..default()
+        })
+        .id();
+    let offscreen = app
+        .world_mut()
+        .spawn(PixelsOptions {
+            width: 64,
+            height: 32,
+            ..default()
+        })
+        .id();
+
+    app.update();
+    assert!(app.world().get::<PixelsBuffer>(empty).is_none());
+
+    app.world_mut()
+        .get_mut::<PixelsOptions>(offscreen)
+        .unwrap()
+        .height = 0;
+    app.update();
+
+    let buffer = app.world().get::<PixelsBuffer>(offscreen).unwrap();
+    assert_eq!((buffer.width(), buffer.height()), (64, 32));
+}
+
+#[test]
+fn offscreen_options_create_and_resize_a_pixels_buffer() {
+    let mut app = App::new();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    let offscreen = app
+        .world_mut()
+        .spawn(PixelsOptions {
+            width: 64,
+            height: 32,
+            ..default()
+        })
+        .id();
+
+    app.update();
+
+    let buffer = app.world().get::<PixelsBuffer>(offscreen).unwrap();
+    assert_eq!((buffer.width(), buffer.height()), (64, 32));
+    assert_eq!(buffer.frame().len(), 64 * 32 * 4);
+
+    let mut options = app.world_mut().get_mut::<PixelsOptions>(offscreen).unwrap();
+    options.width = 16;
+    options.height = 8;
+    app.update();
+
+    let buffer = app.world().get::<PixelsBuffer>(offscreen).unwrap();
+    assert_eq!((buffer.width(), buffer.height()), (16, 8));
+}
+
+#[test]
+fn windows_do_not_receive_offscreen_buffers() {
+    let mut app = App::new();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    let window = app
+        .world_mut()
+        .spawn((Window::default(), PixelsOptions::default()))
+        .id();
+
+    app.update();
+
+    assert!(app.world().get::<PixelsBuffer>(window).is_none());
+}
+
+fn accumulate_previous_frame(mut buffers: Query<(&mut PixelsBuffer, &PreviousFrame)>) {
+    for (mut buffer, previous) in &mut buffers {
+        let last = previous.frame().first().copied().unwrap_or_default();
+        buffer.frame_mut()[0] = last + 1;
+    }
+}
+
+#[test]
+fn previous_frame_holds_last_rendered_frame_during_draw() {
+    let mut app = App::new();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None))
+        .add_systems(Draw, accumulate_previous_frame);
+    let entity = app
+        .world_mut()
+        .spawn((
+            PixelsOptions {
+                width: 2,
+                height: 1,
+                ..default()
+            },
+            PreviousFrame::default(),
+        ))
+        .id();
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let buffer = app.world().get::<PixelsBuffer>(entity).unwrap();
+    assert_eq!(buffer.frame()[0], 3);
+    let previous = app.world().get::<PreviousFrame>(entity).unwrap();
+    assert_eq!((previous.width(), previous.height()), (2, 1));
+    assert_eq!(previous.frame(), buffer.frame());
+}
+
+#[test]
+fn mirror_window_ignores_window_driven_buffer_resizing() {
+    let mut app = App::new();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    let source = app
+        .world_mut()
+        .spawn(PixelsOptions {
+            width: 64,
+            height: 32,
+            ..default()
+        })
+        .id();
+    let mirror = app
+        .world_mut()
+        .spawn((
+            Window {
+                resolution: bevy::window::WindowResolution::new(640, 480),
+                ..default()
+            },
+            PixelsOptions {
+                width: 64,
+                height: 32,
+                scaling_mode: ScalingMode::Fill,
+                ..default()
+            },
+            PixelsSource(source),
+        ))
+        .id();
+
+    app.world_mut().write_message(WindowResized {
+        window: mirror,
+        width: 640.0,
+        height: 480.0,
+    });
+    app.update();
+
+    let options = app.world().get::<PixelsOptions>(mirror).unwrap();
+    assert_eq!((options.width, options.height), (64, 32));
+}
+
+#[test]
+fn palette_resolves_indexed_frame_into_offscreen_buffer() {
+    let mut app = App::new();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    let red = [0xff, 0x00, 0x00, 0xff];
+    let blue = [0x00, 0x00, 0xff, 0xff];
+    let entity = app
+        .world_mut()
+        .spawn((
+            PixelsOptions {
+                width: 2,
+                height: 1,
+                ..default()
+            },
+            Palette::from_colors(&[red, blue]),
+        ))
+        .id();
+
+    app.update();
+
+    let frame = app.world().get::<IndexedFrame>(entity).unwrap();
+    assert_eq!((frame.width(), frame.height()), (2, 1));
+    let buffer = app.world().get::<PixelsBuffer>(entity).unwrap();
+    assert_eq!(buffer.frame(), [red, red].concat());
+
+    app.world_mut()
+        .get_mut::<IndexedFrame>(entity)
+        .unwrap()
+        .set(1, 0, 1);
+    app.update();
+
+    let buffer = app.world().get::<PixelsBuffer>(entity).unwrap();
+    assert_eq!(buffer.frame(), [red, blue].concat());
+
+    app.world_mut()
+        .get_mut::<Palette>(entity)
+        .unwrap()
+        .set_color(0, blue);
+    app.update();
+
+    let buffer = app.world().get::<PixelsBuffer>(entity).unwrap();
+    assert_eq!(buffer.frame(), [blue, blue].concat());
+}
+
+#[test]
+fn palette_cycle_rotates_palette_with_time() {
+    let mut app = App::new();
+    app.add_plugins(PixelsPlugin::default().with_primary_window(None));
+    let mut time = Time::<()>::default();
+    time.advance_by(std::time::Duration::from_secs(1));
+    app.insert_resource(time);
+    let entity = app
+        .world_mut()
+        .spawn((
+            PixelsOptions {
+                width: 1,
+                height: 1,
+                ..default()
+            },
+            Palette::grayscale(),
+            PaletteCycle::new([CycleRange::new(0, 1, 1.0)]),
+        ))
+        .id();
+
+    app.update();
+
+    let buffer = app.world().get::<PixelsBuffer>(entity).unwrap();
+    assert_eq!(buffer.frame(), &[1, 1, 1, 0xff]);
+}